@@ -0,0 +1,83 @@
+// This file is part of Neverest, a CLI to synchronize emails.
+//
+// Copyright (C) 2024-2026  soywod <pimalaya.org@posteo.net>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! `neverest apply` command: applies a plan written by `sync
+//! --plan-out`, once it has been reviewed.
+
+use std::path::PathBuf;
+
+use anyhow::{Result, bail};
+use clap::Parser;
+use pimalaya_cli::{printer::Printer, spinner::Spinner};
+use pimalaya_config::toml::TomlConfig;
+
+use neverest::{
+    config::Config,
+    sync::{self, SyncMode, cache::CacheSnapshot, plan::Plan, pool::Pool},
+};
+
+use crate::cli::{observer::TerminalObserver, sync::ProgressFlags};
+
+/// Applies a saved sync plan. Every mailbox is diffed again first:
+/// planned hunks the fresh diff no longer yields are skipped as stale,
+/// and new changes are left for the next sync.
+#[derive(Debug, Parser)]
+pub struct ApplyCommand {
+    /// Plan file written by `sync --plan-out`.
+    #[arg(value_name = "PLAN")]
+    pub plan: PathBuf,
+
+    /// Print, under every hunk, the live and cached state of both
+    /// sides its reason was read from.
+    #[arg(long, short = 'v')]
+    pub verbose: bool,
+
+    #[command(flatten)]
+    pub progress: ProgressFlags,
+}
+
+impl ApplyCommand {
+    pub fn execute(self, printer: &mut impl Printer, config_paths: &[PathBuf]) -> Result<()> {
+        let plan = Plan::load(&self.plan)?;
+        let progress = self.progress.open()?;
+        let mut config = Config::load_or_wizard(config_paths)?;
+
+        let Some((name, account_config)) = config.take_account(Some(&plan.account))? else {
+            bail!("Cannot find account `{}` of the plan", plan.account);
+        };
+
+        let cache = CacheSnapshot::path(&name)?;
+        if !cache.exists() {
+            bail!("Account `{name}` not initialized, run `init -a {name}` first");
+        }
+
+        let s = Spinner::start("Opening worker pool…");
+        let pool = Pool::open(account_config.left.clone(), account_config.right.clone())?;
+        s.success(format!(
+            "Opened worker pool ({} left, {} right)",
+            pool.left.len(),
+            pool.right.len()
+        ));
+
+        let filter = Some(plan.filter());
+        let mode = SyncMode::ApplyPlan(&plan);
+        let mut observer = (TerminalObserver::new(), progress.for_account(&name));
+        let mut report = sync::run(&name, &account_config, pool, filter, mode, &mut observer)?;
+        report.verbose = self.verbose;
+        printer.out(report)
+    }
+}
@@ -30,10 +30,7 @@ use pimalaya_cli::{
 };
 use pimalaya_config::toml::TomlConfig;
 
-use crate::{
-    client,
-    config::{Config, SideConfig},
-};
+use neverest::config::{Config, SideConfig};
 
 #[derive(Debug, Parser)]
 pub struct CheckCommand {
@@ -61,7 +58,7 @@ impl CheckCommand {
 /// Opens the side and probes it with a `list_mailboxes` call.
 fn check_side(label: &str, config: SideConfig) -> Result<()> {
     let s = Spinner::start(format!("Checking {label} side…"));
-    let mut client = client::open(config)?;
+    let mut client = config.open()?;
     let mailboxes = client.list_mailboxes(false)?;
     s.success(format!(
         "Checked {label} side ({} mailboxes)",
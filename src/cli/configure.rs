@@ -28,7 +28,7 @@ use pimalaya_cli::{
 };
 use pimalaya_config::toml::TomlConfig;
 
-use crate::{config::Config, wizard::edit::edit_account};
+use neverest::config::Config;
 
 #[derive(Debug, Parser)]
 pub struct ConfigureCommand {
@@ -54,7 +54,7 @@ impl ConfigureCommand {
         };
 
         let target = Config::target_path(config_paths)?;
-        edit_account(&target, config, &name)?;
+        config.edit_account(&target, &name)?;
 
         printer.out(Message::new(format!("Account `{name}` configured")))
     }
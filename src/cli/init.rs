@@ -32,7 +32,7 @@ use pimalaya_cli::{
 };
 use pimalaya_config::toml::TomlConfig;
 
-use crate::{client, config::Config, sync::cache::CacheSnapshot};
+use neverest::{config::Config, sync::cache::CacheSnapshot};
 
 /// Initializes an account's per-side state; refuses to run if it is
 /// already initialized.
@@ -58,11 +58,19 @@ impl InitCommand {
         }
 
         let s = Spinner::start("Initializing left side…");
-        client::init(account_config.left.clone()).context("Initialize left side")?;
+        account_config
+            .left
+            .clone()
+            .init()
+            .context("Initialize left side")?;
         s.success("Initialized left side");
 
         let s = Spinner::start("Initializing right side…");
-        client::init(account_config.right.clone()).context("Initialize right side")?;
+        account_config
+            .right
+            .clone()
+            .init()
+            .context("Initialize right side")?;
         s.success("Initialized right side");
 
         let s = Spinner::start("Writing initial cache snapshot…");
@@ -0,0 +1,106 @@
+// This file is part of Neverest, a CLI to synchronize emails.
+//
+// Copyright (C) 2024-2026  soywod <pimalaya.org@posteo.net>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! `neverest log` command: queries the history of past syncs, to find
+//! out when and why a message was copied, flagged or deleted.
+
+use std::path::PathBuf;
+
+use anyhow::{Result, bail};
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone};
+use clap::Parser;
+use pimalaya_cli::{clap::args::AccountFlag, printer::Printer};
+use pimalaya_config::toml::TomlConfig;
+
+use neverest::{
+    config::Config,
+    sync::history::{self, HistoryFilter, HistoryLog},
+};
+
+/// Shows past syncs of the account, oldest first, with the hunks they
+/// applied, rejected or were blocked on.
+#[derive(Debug, Parser)]
+pub struct LogCommand {
+    #[command(flatten)]
+    pub account: AccountFlag,
+
+    /// Only show hunks about this mailbox.
+    #[arg(long, short, value_name = "MAILBOX")]
+    pub mailbox: Option<String>,
+
+    /// Only show hunks about the message with this `Message-ID:`
+    /// header, angle brackets optional.
+    #[arg(long, value_name = "ID")]
+    pub message_id: Option<String>,
+
+    /// Only show syncs since this time: RFC 3339, a `YYYY-MM-DD` date
+    /// (local midnight), or a duration ago like `30m`, `12h`, `7d`,
+    /// `2w`.
+    #[arg(long, value_name = "TIME", value_parser = parse_since)]
+    pub since: Option<DateTime<Local>>,
+}
+
+impl LogCommand {
+    pub fn execute(self, printer: &mut impl Printer, config_paths: &[PathBuf]) -> Result<()> {
+        let mut config = Config::load_or_wizard(config_paths)?;
+
+        let account_name = self.account.name.as_deref();
+        let Some((name, _)) = config.take_account(account_name)? else {
+            bail!("Cannot find account");
+        };
+
+        let filter = HistoryFilter {
+            mailbox: self.mailbox,
+            message_id: self.message_id,
+            since: self.since,
+        };
+
+        let records = history::load(&history::path(&name)?)?
+            .into_iter()
+            .filter_map(|record| filter.apply(record))
+            .collect();
+
+        printer.out(HistoryLog { records })
+    }
+}
+
+fn parse_since(value: &str) -> Result<DateTime<Local>, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Local));
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+        return Local
+            .from_local_datetime(&midnight)
+            .earliest()
+            .ok_or_else(|| format!("`{value}` is not a valid local date"));
+    }
+
+    let invalid = || format!("expected RFC 3339 time, YYYY-MM-DD date or duration, got `{value}`");
+    let split = value.len().checked_sub(1).ok_or_else(invalid)?;
+    let (n, unit) = value.split_at(split);
+    let n: i64 = n.parse().map_err(|_| invalid())?;
+    let ago = match unit {
+        "m" => Duration::minutes(n),
+        "h" => Duration::hours(n),
+        "d" => Duration::days(n),
+        "w" => Duration::weeks(n),
+        _ => return Err(invalid()),
+    };
+    Ok(Local::now() - ago)
+}
@@ -32,7 +32,8 @@ use pimalaya_cli::{
 };
 
 use crate::cli::{
-    check::CheckCommand, configure::ConfigureCommand, init::InitCommand, sync::SyncCommand,
+    apply::ApplyCommand, check::CheckCommand, configure::ConfigureCommand, init::InitCommand,
+    log::LogCommand, sync::SyncCommand,
 };
 
 #[derive(Parser, Debug)]
@@ -64,6 +65,8 @@ pub enum Command {
     Check(CheckCommand),
     Init(InitCommand),
     Sync(SyncCommand),
+    Apply(ApplyCommand),
+    Log(LogCommand),
     #[command(alias = "cfg")]
     Configure(ConfigureCommand),
     #[command(arg_required_else_help = true)]
@@ -78,6 +81,8 @@ impl Command {
             Self::Check(cmd) => cmd.execute(printer, config_paths),
             Self::Init(cmd) => cmd.execute(printer, config_paths),
             Self::Sync(cmd) => cmd.execute(printer, config_paths),
+            Self::Apply(cmd) => cmd.execute(printer, config_paths),
+            Self::Log(cmd) => cmd.execute(printer, config_paths),
             Self::Configure(cmd) => cmd.execute(printer, config_paths),
             Self::Manuals(cmd) => cmd.execute(printer, Cli::command()),
             Self::Completions(cmd) => cmd.execute(printer, Cli::command()),
@@ -17,8 +17,12 @@
 
 //! Clap-driven CLI: top-level parser plus one module per subcommand.
 
+pub mod apply;
 pub mod check;
 pub mod configure;
 pub mod init;
+pub mod log;
 pub mod main;
+pub mod observer;
+pub mod review;
 pub mod sync;
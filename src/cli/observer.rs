@@ -0,0 +1,93 @@
+// This file is part of Neverest, a CLI to synchronize emails.
+//
+// Copyright (C) 2024-2026  soywod <pimalaya.org@posteo.net>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Terminal [`SyncObserver`]: one spinner per stage, and the
+//! crossterm review of `sync --interactive`.
+
+use anyhow::{Result, bail};
+use pimalaya_cli::spinner::Spinner;
+
+use neverest::sync::{
+    observer::{Stage, SyncObserver},
+    review::ReviewItem,
+};
+
+use crate::cli::review::TerminalReview;
+
+pub struct TerminalObserver {
+    /// Draws spinners; off when several accounts share the terminal.
+    spinners: bool,
+    spinner: Option<Spinner>,
+    review: Option<TerminalReview>,
+}
+
+impl TerminalObserver {
+    /// Spinners only; reviews are refused.
+    pub fn new() -> Self {
+        Self {
+            spinners: true,
+            spinner: None,
+            review: None,
+        }
+    }
+
+    /// Draws nothing, for accounts synced in parallel: their spinners
+    /// would interleave on the same stderr, the final report sums them
+    /// up instead.
+    pub fn silent() -> Self {
+        Self {
+            spinners: false,
+            ..Self::new()
+        }
+    }
+
+    /// Reviews drawn by `review`.
+    pub fn with_review(mut self, review: TerminalReview) -> Self {
+        self.review = Some(review);
+        self
+    }
+
+    /// Starts a spinner outside of a sync stage, unless silent.
+    pub fn spinner(&self, message: &str) -> Option<Spinner> {
+        self.spinners.then(|| Spinner::start(message.to_owned()))
+    }
+}
+
+impl SyncObserver for TerminalObserver {
+    fn stage_started(&mut self, _stage: Stage, message: &str) {
+        self.spinner = self.spinner(message);
+    }
+
+    fn stage_progress(&mut self, _stage: Stage, message: &str) {
+        if let Some(spinner) = &self.spinner {
+            spinner.set_message(message.to_owned());
+        }
+    }
+
+    fn stage_finished(&mut self, _stage: Stage, message: &str, _elapsed_ms: u64) {
+        if let Some(spinner) = self.spinner.take() {
+            spinner.success(message.to_owned());
+        }
+    }
+
+    fn review(&mut self, title: &str, items: &[ReviewItem]) -> Result<Vec<bool>> {
+        let Some(review) = &mut self.review else {
+            bail!("Cannot review hunks without a terminal");
+        };
+        review.review(title, items)
+    }
+}
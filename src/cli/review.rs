@@ -0,0 +1,312 @@
+// This file is part of Neverest, a CLI to synchronize emails.
+//
+// Copyright (C) 2024-2026  soywod <pimalaya.org@posteo.net>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Terminal front-end of `sync --interactive`: lists the hunks grouped
+//! per mailbox on the alternate screen and lets the user approve or
+//! reject them one by one, per category or per mailbox.
+
+use std::{
+    collections::BTreeMap,
+    io::{self, IsTerminal, Stderr, Write},
+};
+
+use anyhow::{Context, Result, bail};
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::{Print, Stylize},
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+
+use neverest::sync::review::ReviewItem;
+
+/// Lines above the hunk list: title, key help, blank line.
+const HEADER_LINES: u16 = 3;
+
+const HELP: &str =
+    "↑↓ move · space toggle · c category · m mailbox · a/r all · enter apply · q abort";
+
+/// Hunk review drawn with crossterm on stderr, keeping stdout free for
+/// the report.
+pub struct TerminalReview;
+
+impl TerminalReview {
+    pub fn new() -> Result<Self> {
+        if !io::stderr().is_terminal() {
+            bail!("Interactive review needs a terminal on stderr");
+        }
+        Ok(Self)
+    }
+
+    /// Lets the user approve or reject `items`, see
+    /// [`SyncObserver::review`](neverest::sync::observer::SyncObserver::review).
+    pub fn review(&mut self, title: &str, items: &[ReviewItem]) -> Result<Vec<bool>> {
+        let mut out = io::stderr();
+        let _screen = RawScreen::enter(&mut out)?;
+        let mut state = ReviewState::new(items);
+
+        loop {
+            let (width, height) = terminal::size().context("Read terminal size")?;
+            let rows = height.saturating_sub(HEADER_LINES).max(1) as usize;
+            state.scroll(rows);
+            draw(&mut out, title, &state, width as usize, rows)?;
+
+            let key = match event::read().context("Read terminal event")? {
+                Event::Key(key) if key.kind != KeyEventKind::Release => key,
+                _ => continue,
+            };
+            match key {
+                KeyEvent {
+                    code: KeyCode::Char('c'),
+                    modifiers: KeyModifiers::CONTROL,
+                    ..
+                } => bail!("Review aborted"),
+                KeyEvent { code, .. } => match code {
+                    KeyCode::Up | KeyCode::Char('k') => state.move_by(-1),
+                    KeyCode::Down | KeyCode::Char('j') => state.move_by(1),
+                    KeyCode::PageUp => state.move_by(-(rows as isize)),
+                    KeyCode::PageDown => state.move_by(rows as isize),
+                    KeyCode::Home | KeyCode::Char('g') => state.move_by(isize::MIN),
+                    KeyCode::End | KeyCode::Char('G') => state.move_by(isize::MAX),
+                    KeyCode::Char(' ') => state.toggle_current(),
+                    KeyCode::Char('c') => state.toggle_category(),
+                    KeyCode::Char('m') => state.toggle_mailbox(),
+                    KeyCode::Char('a') => state.set_all(true),
+                    KeyCode::Char('r') => state.set_all(false),
+                    KeyCode::Enter | KeyCode::Char('y') => return Ok(state.approved),
+                    KeyCode::Esc | KeyCode::Char('q') => bail!("Review aborted"),
+                    _ => {}
+                },
+            }
+        }
+    }
+}
+
+/// Raw mode on the alternate screen, restored on drop (also when the
+/// review bails).
+struct RawScreen;
+
+impl RawScreen {
+    fn enter(out: &mut Stderr) -> Result<Self> {
+        terminal::enable_raw_mode().context("Enable terminal raw mode")?;
+        let screen = Self;
+        execute!(out, EnterAlternateScreen, Hide).context("Enter alternate screen")?;
+        Ok(screen)
+    }
+}
+
+impl Drop for RawScreen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stderr(), Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Display line of the list: a mailbox header or one of its hunks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Line<'a> {
+    Mailbox(&'a str),
+    Item(usize),
+}
+
+/// Decisions taken so far, plus the cursor over `lines`.
+struct ReviewState<'a> {
+    items: &'a [ReviewItem],
+    /// Every hunk starts approved, as in a plain sync.
+    approved: Vec<bool>,
+    lines: Vec<Line<'a>>,
+    /// Index into `lines`, always on an item.
+    cursor: usize,
+    /// First line shown.
+    offset: usize,
+}
+
+impl<'a> ReviewState<'a> {
+    fn new(items: &'a [ReviewItem]) -> Self {
+        let mut groups: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+        for (i, item) in items.iter().enumerate() {
+            groups.entry(item.mailbox.as_str()).or_default().push(i);
+        }
+
+        let mut lines = Vec::with_capacity(items.len() + groups.len());
+        for (mailbox, indexes) in groups {
+            lines.push(Line::Mailbox(mailbox));
+            lines.extend(indexes.into_iter().map(Line::Item));
+        }
+
+        Self {
+            items,
+            approved: vec![true; items.len()],
+            cursor: lines
+                .iter()
+                .position(|l| matches!(l, Line::Item(_)))
+                .unwrap_or(0),
+            lines,
+            offset: 0,
+        }
+    }
+
+    fn current(&self) -> Option<usize> {
+        match self.lines.get(self.cursor) {
+            Some(Line::Item(i)) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// Moves the cursor by `delta` items, skipping mailbox headers.
+    fn move_by(&mut self, delta: isize) {
+        let items: Vec<usize> = (0..self.lines.len())
+            .filter(|l| matches!(self.lines[*l], Line::Item(_)))
+            .collect();
+        let Some(pos) = items.iter().position(|l| *l == self.cursor) else {
+            return;
+        };
+        let target = pos.saturating_add_signed(delta).min(items.len() - 1);
+        self.cursor = items[target];
+    }
+
+    /// Keeps the cursor within the `rows` lines shown, along with the
+    /// header of its mailbox when it fits.
+    fn scroll(&mut self, rows: usize) {
+        let top = self.cursor.saturating_sub(1);
+        if top < self.offset {
+            self.offset = top;
+        } else if self.cursor >= self.offset + rows {
+            self.offset = self.cursor + 1 - rows;
+        }
+    }
+
+    fn toggle_current(&mut self) {
+        if let Some(i) = self.current() {
+            self.approved[i] = !self.approved[i];
+        }
+    }
+
+    /// Rejects every hunk of the current one's category when any is
+    /// approved, approves them all otherwise.
+    fn toggle_category(&mut self) {
+        if let Some(i) = self.current() {
+            let category = self.items[i].category;
+            self.toggle_where(|item| item.category == category);
+        }
+    }
+
+    /// Same as [`Self::toggle_category`] for the current mailbox.
+    fn toggle_mailbox(&mut self) {
+        if let Some(i) = self.current() {
+            let mailbox = self.items[i].mailbox.clone();
+            self.toggle_where(|item| item.mailbox == mailbox);
+        }
+    }
+
+    fn toggle_where(&mut self, matches: impl Fn(&ReviewItem) -> bool) {
+        let indexes: Vec<usize> = (0..self.items.len())
+            .filter(|i| matches(&self.items[*i]))
+            .collect();
+        let approve = !indexes.iter().any(|i| self.approved[*i]);
+        for i in indexes {
+            self.approved[i] = approve;
+        }
+    }
+
+    fn set_all(&mut self, approved: bool) {
+        self.approved.fill(approved);
+    }
+}
+
+fn draw(
+    out: &mut Stderr,
+    title: &str,
+    state: &ReviewState<'_>,
+    width: usize,
+    rows: usize,
+) -> Result<()> {
+    let approved = state.approved.iter().filter(|a| **a).count();
+    let total = state.items.len();
+
+    queue!(out, Clear(ClearType::All), MoveTo(0, 0))?;
+    let heading = format!("{title}: {approved} of {total} hunks approved");
+    queue!(out, Print(truncate(&heading, width).bold()))?;
+    queue!(out, MoveTo(0, 1), Print(truncate(HELP, width).dim()))?;
+
+    let shown = state.lines.iter().enumerate().skip(state.offset).take(rows);
+    for (row, (l, line)) in shown.enumerate() {
+        queue!(out, MoveTo(0, HEADER_LINES + row as u16))?;
+        match line {
+            Line::Mailbox(mailbox) => {
+                queue!(out, Print(truncate(mailbox, width).bold().underlined()))?;
+            }
+            Line::Item(i) => {
+                let item = &state.items[*i];
+                let mark = if state.approved[*i] { 'x' } else { ' ' };
+                let text = format!("  [{mark}] {:<12} {}", item.category, item.label);
+                let text = truncate(&text, width);
+                match (l == state.cursor, state.approved[*i]) {
+                    (true, _) => queue!(out, Print(text.reverse()))?,
+                    (false, true) => queue!(out, Print(text))?,
+                    (false, false) => queue!(out, Print(text.dim()))?,
+                }
+            }
+        }
+    }
+
+    out.flush().context("Draw review screen")?;
+    Ok(())
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(mailbox: &str, category: &'static str) -> ReviewItem {
+        ReviewItem {
+            mailbox: mailbox.into(),
+            category,
+            label: String::new(),
+        }
+    }
+
+    #[test]
+    fn cursor_skips_headers_and_toggles_whole_categories() {
+        let items = [
+            item("INBOX", "copy"),
+            item("Archive", "delete"),
+            item("INBOX", "delete"),
+        ];
+        let mut state = ReviewState::new(&items);
+
+        // Archive sorts first: its header, then its delete hunk.
+        assert_eq!(state.current(), Some(1));
+        state.move_by(1);
+        assert_eq!(state.current(), Some(0));
+
+        state.move_by(1);
+        state.toggle_category();
+        assert_eq!(state.approved, vec![true, false, false]);
+        state.toggle_category();
+        assert_eq!(state.approved, vec![true, true, true]);
+
+        state.toggle_current();
+        state.toggle_mailbox();
+        assert_eq!(state.approved, vec![false, true, false]);
+    }
+}
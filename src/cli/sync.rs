@@ -16,33 +16,77 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 //! `neverest sync` command: opens the worker pool, runs the sync and
-//! prints the resulting [`crate::sync::report::SyncReport`].
+//! prints the resulting [`neverest::sync::report::SyncReport`], or a
+//! [`neverest::sync::report::MultiSyncReport`] when several accounts are
+//! synchronized at once.
 
-use std::path::PathBuf;
+use std::{fs::File, io, path::PathBuf, sync::mpsc, thread};
 
 use anyhow::{Context, Result, bail};
-use clap::{ArgAction, Parser};
+use clap::{ArgAction, Args, Parser, ValueEnum};
+use crossbeam_queue::SegQueue;
 use log::info;
-use pimalaya_cli::{clap::args::AccountFlag, printer::Printer, spinner::Spinner};
+use pimalaya_cli::printer::Printer;
 use pimalaya_config::toml::TomlConfig;
 
-use crate::{
-    config::{Config, MailboxFilter},
-    sync::{self, cache::CacheSnapshot, pool::Pool},
+use neverest::{
+    config::{AccountConfig, Config, MailboxFilter},
+    sync::{
+        self, SyncMode,
+        cache::CacheSnapshot,
+        plan::Plan,
+        pool::Pool,
+        progress::Progress,
+        report::{AccountSyncOutcome, MultiSyncReport, SyncReport},
+    },
 };
 
+use crate::cli::{observer::TerminalObserver, review::TerminalReview};
+
 /// Synchronizes mailboxes and messages between the configured left and
 /// right sides.
 #[derive(Debug, Parser)]
 pub struct SyncCommand {
-    #[command(flatten)]
-    pub account: AccountFlag,
+    /// Synchronize the given account (repeatable); defaults to the
+    /// account flagged `default = true`.
+    #[arg(long = "account", short = 'a')]
+    #[arg(value_name = "NAME", action = ArgAction::Append)]
+    #[arg(conflicts_with = "all_accounts")]
+    pub accounts: Vec<String>,
+
+    /// Synchronize every configured account.
+    #[arg(long = "all", conflicts_with = "accounts")]
+    pub all_accounts: bool,
+
+    /// Maximum number of accounts synchronized in parallel.
+    #[arg(long, short = 'j', value_name = "N", default_value_t = 4)]
+    pub jobs: usize,
 
     /// Run the synchronization without applying any changes; only
     /// prints the patch that would have been applied.
     #[arg(long, short = 'd')]
     pub dry_run: bool,
 
+    /// Print, under every hunk, the live and cached state of both
+    /// sides its reason was read from.
+    #[arg(long, short = 'v')]
+    pub verbose: bool,
+
+    /// Review the mailbox and message patches in a terminal UI before
+    /// applying them; only approved hunks are applied. Single account
+    /// only.
+    #[arg(long, short = 'i', conflicts_with = "dry_run")]
+    #[arg(conflicts_with = "all_accounts")]
+    pub interactive: bool,
+
+    /// Apply nothing; write the patch and the state it was computed
+    /// against to the given file, for `neverest apply` once reviewed.
+    /// Single account only.
+    #[arg(long, value_name = "PATH")]
+    #[arg(conflicts_with = "dry_run", conflicts_with = "interactive")]
+    #[arg(conflicts_with = "all_accounts")]
+    pub plan_out: Option<PathBuf>,
+
     /// Synchronize only the given mailbox names (repeatable, ASCII
     /// case-insensitive).
     #[arg(long, short = 'm')]
@@ -65,18 +109,203 @@ pub struct SyncCommand {
     /// `--include-mailbox` entries when set.
     #[arg(long)]
     pub reset: bool,
+
+    #[command(flatten)]
+    pub progress: ProgressFlags,
+}
+
+/// Machine-readable progress, for frontends following a long sync.
+#[derive(Debug, Args)]
+pub struct ProgressFlags {
+    /// Emit progress events (stages, mailboxes, hunks) in this format,
+    /// on stderr unless `--progress-fd` is given.
+    #[arg(long, value_name = "FORMAT")]
+    pub progress: Option<ProgressFormat>,
+
+    /// Write progress events to this already open file descriptor
+    /// instead of stderr.
+    #[arg(long, value_name = "FD", requires = "progress")]
+    pub progress_fd: Option<u32>,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ProgressFormat {
+    /// One JSON object per line.
+    Jsonl,
+}
+
+impl ProgressFlags {
+    /// Opens the progress sink; silent when `--progress` is not set.
+    pub fn open(&self) -> Result<Progress> {
+        let Some(ProgressFormat::Jsonl) = self.progress else {
+            return Ok(Progress::default());
+        };
+
+        match self.progress_fd {
+            None => Ok(Progress::jsonl(io::stderr())),
+            Some(fd) => Ok(Progress::jsonl(progress_file(fd)?)),
+        }
+    }
+}
+
+/// Writes to a duplicate of the already open file descriptor `fd`,
+/// which stays open on its own (stdout and stderr included).
+#[cfg(unix)]
+fn progress_file(fd: u32) -> Result<File> {
+    use std::os::fd::{BorrowedFd, RawFd};
+
+    if fd == 0 {
+        bail!("Cannot write progress to file descriptor 0, which is stdin");
+    }
+    let raw = RawFd::try_from(fd).context(format!("Invalid progress file descriptor {fd}"))?;
+    // SAFETY: the descriptor is only borrowed for the dup below; a
+    // descriptor that is not open makes the dup fail with EBADF.
+    let borrowed = unsafe { BorrowedFd::borrow_raw(raw) };
+    let owned = borrowed
+        .try_clone_to_owned()
+        .context(format!("Progress file descriptor {fd} is not open"))?;
+    Ok(File::from(owned))
+}
+
+#[cfg(not(unix))]
+fn progress_file(fd: u32) -> Result<File> {
+    bail!("Cannot write progress to file descriptor {fd}: --progress-fd is only supported on Unix")
 }
 
 impl SyncCommand {
     pub fn execute(self, printer: &mut impl Printer, config_paths: &[PathBuf]) -> Result<()> {
         let mut config = Config::load_or_wizard(config_paths)?;
+        let mut accounts = self.take_accounts(&mut config)?;
+        let progress = self.progress.open()?;
 
-        let account_name = self.account.name.as_deref();
-        let Some((name, account_config)) = config.take_account(account_name)? else {
-            bail!("Cannot find account");
-        };
+        if self.interactive && accounts.len() > 1 {
+            bail!("Cannot review several accounts at once, pick one with --account");
+        }
+        if self.plan_out.is_some() && accounts.len() > 1 {
+            bail!("Cannot plan several accounts at once, pick one with --account");
+        }
+
+        if accounts.len() == 1 {
+            let (name, account_config) = accounts.remove(0);
+            let terminal = TerminalObserver::new();
+            let report = self.sync_account(&name, &account_config, &progress, terminal)?;
+            return printer.out(report);
+        }
 
-        let cache = CacheSnapshot::path(&name)?;
+        let report = self.sync_accounts(accounts, &progress)?;
+        let failed = report.failed();
+        let total = report.accounts.len();
+        printer.out(report)?;
+
+        if failed > 0 {
+            bail!("{failed} of {total} accounts failed to synchronize");
+        }
+
+        Ok(())
+    }
+
+    /// Resolves the accounts selected by `--all`, the repeatable
+    /// `--account` or the default account, in that order.
+    fn take_accounts(&self, config: &mut Config) -> Result<Vec<(String, AccountConfig)>> {
+        if self.all_accounts {
+            let mut accounts: Vec<_> = config.accounts.drain().collect();
+            if accounts.is_empty() {
+                bail!("Cannot find any account");
+            }
+            accounts.sort_by(|(a, _), (b, _)| a.cmp(b));
+            return Ok(accounts);
+        }
+
+        if self.accounts.is_empty() {
+            let Some(account) = config.take_account(None)? else {
+                bail!("Cannot find account");
+            };
+            return Ok(vec![account]);
+        }
+
+        let mut accounts = Vec::with_capacity(self.accounts.len());
+        for name in &self.accounts {
+            // NOTE: `-a foo -a foo` must not sync the same account
+            // twice in parallel against the same cache file.
+            if accounts.iter().any(|(taken, _)| taken == name) {
+                continue;
+            }
+            let Some(account) = config.take_account(Some(name.as_str()))? else {
+                bail!("Cannot find account `{name}`");
+            };
+            accounts.push(account);
+        }
+
+        Ok(accounts)
+    }
+
+    /// Fans `accounts` out across `--jobs` threads; an account failing
+    /// to sync is recorded in the report without stopping the others.
+    /// Spinners are off: the final report is the only terminal output.
+    fn sync_accounts(
+        &self,
+        accounts: Vec<(String, AccountConfig)>,
+        progress: &Progress,
+    ) -> Result<MultiSyncReport> {
+        let total = accounts.len();
+        let jobs = self.jobs.clamp(1, total);
+
+        let queue: SegQueue<(String, AccountConfig)> = SegQueue::new();
+        for account in accounts {
+            queue.push(account);
+        }
+        let (done_tx, done_rx) = mpsc::channel::<AccountSyncOutcome>();
+
+        let mut outcomes: Vec<AccountSyncOutcome> = Vec::with_capacity(total);
+
+        thread::scope(|scope| -> Result<()> {
+            let mut handles = Vec::with_capacity(jobs);
+            for _ in 0..jobs {
+                let queue = &queue;
+                let tx = done_tx.clone();
+                handles.push(scope.spawn(move || {
+                    while let Some((name, account_config)) = queue.pop() {
+                        let terminal = TerminalObserver::silent();
+                        let result = self.sync_account(&name, &account_config, progress, terminal);
+                        let outcome = match result {
+                            Ok(report) => AccountSyncOutcome::success(name, report),
+                            Err(err) => AccountSyncOutcome::failure(name, err),
+                        };
+                        if tx.send(outcome).is_err() {
+                            break;
+                        }
+                    }
+                }));
+            }
+            drop(done_tx);
+
+            while let Ok(outcome) = done_rx.recv() {
+                outcomes.push(outcome);
+            }
+
+            for handle in handles {
+                if handle.join().is_err() {
+                    bail!("Account sync thread panicked");
+                }
+            }
+            Ok(())
+        })?;
+
+        outcomes.sort_by(|a, b| a.account.cmp(&b.account));
+
+        Ok(MultiSyncReport { accounts: outcomes })
+    }
+
+    /// Synchronizes a single account end-to-end: cache checks, optional
+    /// reset, pool open and [`sync::run`].
+    fn sync_account(
+        &self,
+        name: &str,
+        account_config: &AccountConfig,
+        progress: &Progress,
+        terminal: TerminalObserver,
+    ) -> Result<SyncReport> {
+        let cache = CacheSnapshot::path(name)?;
         if !cache.exists() {
             bail!("Account `{name}` not initialized, run `init -a {name}` first");
         }
@@ -97,14 +326,32 @@ impl SyncCommand {
             }
         }
 
-        let s = Spinner::start("Opening worker pool…");
+        let s = terminal.spinner("Opening worker pool…");
         let pool = Pool::open(account_config.left.clone(), account_config.right.clone())?;
-        s.success(format!(
-            "Opened worker pool ({} left, {} right)",
-            pool.left.len(),
-            pool.right.len()
-        ));
+        if let Some(s) = s {
+            s.success(format!(
+                "Opened worker pool ({} left, {} right)",
+                pool.left.len(),
+                pool.right.len()
+            ));
+        }
 
+        let observer = (terminal, progress.for_account(name));
+        let mut report = self.run_mode(name, account_config, pool, observer)?;
+        report.verbose = self.verbose;
+        Ok(report)
+    }
+
+    /// Runs [`sync::run`] in the mode selected by `--plan-out`,
+    /// `--interactive` or `--dry-run`, observed by the terminal and
+    /// the progress stream.
+    fn run_mode(
+        &self,
+        name: &str,
+        account_config: &AccountConfig,
+        pool: Pool,
+        mut observer: (TerminalObserver, Progress),
+    ) -> Result<SyncReport> {
         let cli_filter = if !self.include_mailbox.is_empty() {
             Some(MailboxFilter::Include(self.include_mailbox.clone()))
         } else if !self.exclude_mailbox.is_empty() {
@@ -115,8 +362,50 @@ impl SyncCommand {
             None
         };
 
-        let report = sync::run(&name, &account_config, pool, cli_filter, self.dry_run)?;
+        if let Some(path) = &self.plan_out {
+            let mut plan = Plan::new(name);
+            let mode = SyncMode::Plan(&mut plan);
+            let report = sync::run(name, account_config, pool, cli_filter, mode, &mut observer)?;
+            plan.save(path)?;
+            info!(
+                "wrote {} planned hunks to `{}`",
+                plan.hunk_count(),
+                path.display()
+            );
+            return Ok(report);
+        }
+
+        if self.interactive {
+            let (terminal, progress) = observer;
+            let terminal = terminal.with_review(TerminalReview::new()?);
+            let mut observer = (terminal, progress);
+            let mode = SyncMode::Review;
+            return sync::run(name, account_config, pool, cli_filter, mode, &mut observer);
+        }
+
+        let mode = if self.dry_run {
+            SyncMode::DryRun
+        } else {
+            SyncMode::Apply
+        };
+        sync::run(name, account_config, pool, cli_filter, mode, &mut observer)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::fd::AsRawFd;
+
+    use super::*;
+
+    #[test]
+    fn progress_fd_is_duplicated_and_never_stdin() {
+        assert!(progress_file(0).is_err());
 
-        printer.out(report)
+        let file = progress_file(2).unwrap();
+        assert_ne!(file.as_raw_fd(), 2);
+        drop(file);
+        // Dropping the duplicate left stderr open.
+        assert!(progress_file(2).is_ok());
     }
 }
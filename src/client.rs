@@ -17,10 +17,18 @@
 
 //! Side-agnostic protocol client construction for the sync engine.
 
+use std::{collections::HashSet, path::PathBuf};
+
 use anyhow::{Result, bail};
 #[cfg(feature = "jmap")]
 use base64::{Engine, prelude::BASE64_STANDARD};
-use io_email::client::EmailClientStd;
+#[cfg(feature = "m2dir")]
+use io_email::m2dir::convert::open_m2dir;
+use io_email::{
+    client::{EmailClientStd, EmailClientStdError},
+    envelope::EnvelopeDiff,
+    flag::Flag,
+};
 #[cfg(feature = "imap")]
 use io_imap::client::ImapClientStd;
 #[cfg(feature = "jmap")]
@@ -40,6 +48,55 @@ use url::{ParseError, Url};
 use crate::config::JmapAuthConfig;
 use crate::config::SideConfig;
 
+/// Issues a SELECT on `client` when its backend is IMAP, otherwise a
+/// no-op; primes workers for an `auto_select=false` hunk batch.
+#[cfg(feature = "imap")]
+pub fn imap_select(client: &mut EmailClientStd, mailbox: &str) -> Result<()> {
+    if let Some(imap) = client.as_imap_mut() {
+        imap.select(mailbox.to_owned().try_into()?)?;
+    }
+    Ok(())
+}
+
+/// Mailbox status reported by an IMAP SELECT.
+#[cfg(feature = "imap")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ImapSelected {
+    pub exists: u32,
+    pub uid_validity: Option<u32>,
+}
+
+/// Like [`imap_select`], returning the mailbox status; `None` when
+/// `client` is not IMAP.
+#[cfg(feature = "imap")]
+pub fn imap_select_status(
+    client: &mut EmailClientStd,
+    mailbox: &str,
+) -> Result<Option<ImapSelected>> {
+    let Some(imap) = client.as_imap_mut() else {
+        return Ok(None);
+    };
+    let data = imap.select(mailbox.to_owned().try_into()?)?;
+    Ok(Some(ImapSelected {
+        exists: data.exists,
+        uid_validity: data.uid_validity,
+    }))
+}
+
+/// Path of message `id` in `mailbox` on an m2dir side; `None` on
+/// remote sides, whose messages have no path.
+#[cfg_attr(not(feature = "m2dir"), allow(unused_variables))]
+pub fn message_path(config: &SideConfig, mailbox: &str, id: &str) -> Result<Option<PathBuf>> {
+    #[cfg(feature = "m2dir")]
+    if let SideConfig::M2dir(config) = config {
+        let mut client = M2dirClient::new(config.root.to_string_lossy().into_owned());
+        let m2dir = open_m2dir(&mut client, mailbox)?;
+        let (entry, _) = client.get(m2dir, id)?;
+        return Ok(Some(entry.path().to_owned()));
+    }
+    Ok(None)
+}
+
 /// Opens the protocol client for `config` and registers it onto a fresh
 /// [`EmailClientStd`].
 ///
@@ -115,6 +172,104 @@ pub fn open(config: SideConfig) -> Result<EmailClientStd> {
     }
 }
 
+/// Precondition of flag and delete writes: the messages must not
+/// have changed since the listing their hunks were diffed from.
+///
+/// Checked through the `io-email` envelope diff (JMAP `Email/changes`,
+/// IMAP QRESYNC) from the state that listing returned, once per write
+/// request. The check and the write are two requests, so a change
+/// landing in between still slips through.
+// TODO: send the write itself conditionally (IMAP UID STORE
+// UNCHANGEDSINCE, JMAP `ifInState`) once `io-imap` and `io-jmap`
+// expose it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WriteGuard {
+    /// Envelope-diff state, moved forward by each check.
+    state: Vec<u8>,
+    /// Ids changed by others since the listing.
+    changed: HashSet<String>,
+    /// Ids cleared for one of our writes: their later changes are
+    /// ours, not others'.
+    own: HashSet<String>,
+}
+
+/// Flag or delete write, cleared by a [`WriteGuard`] when the side
+/// has one.
+#[derive(Clone, Copy, Debug)]
+pub enum GuardedWrite<'a> {
+    AddFlags(&'a [Flag]),
+    RemoveFlags(&'a [Flag]),
+    Delete,
+}
+
+impl WriteGuard {
+    /// Guard from the envelope-diff `state` of a listing.
+    pub fn new(state: Vec<u8>) -> Self {
+        Self {
+            state,
+            changed: HashSet::new(),
+            own: HashSet::new(),
+        }
+    }
+
+    /// Moves the state forward and returns the `ids` changed by others
+    /// since the listing; the other ids are cleared for our write.
+    /// Backends that cannot diff from the state clear every id.
+    pub fn check(
+        &mut self,
+        client: &mut EmailClientStd,
+        mailbox: &str,
+        ids: &[&str],
+    ) -> Result<Vec<String>> {
+        match client.diff_envelopes(mailbox, Some(&self.state)) {
+            Ok(EnvelopeDiff::Incremental {
+                new_state,
+                flag_updates,
+                vanished_ids,
+                ..
+            }) => {
+                let changed = flag_updates.into_iter().map(|update| update.id);
+                let changed = changed.chain(vanished_ids);
+                // NOTE: our own writes move the state too; they must
+                // not trip the next check.
+                let own = &self.own;
+                self.changed.extend(changed.filter(|id| !own.contains(id)));
+                self.state = new_state;
+            }
+            Ok(EnvelopeDiff::FullListRequired { .. })
+            | Err(EmailClientStdError::UnsupportedOperation) => {}
+            Err(err) => return Err(err.into()),
+        }
+
+        let (modified, cleared): (Vec<&str>, Vec<&str>) =
+            ids.iter().partition(|id| self.changed.contains(**id));
+        self.own.extend(cleared.into_iter().map(str::to_owned));
+        Ok(modified.into_iter().map(str::to_owned).collect())
+    }
+}
+
+/// Applies `write` to `ids`, with no precondition of its own.
+pub fn write_unguarded(
+    client: &mut EmailClientStd,
+    mailbox: &str,
+    ids: &[&str],
+    write: GuardedWrite<'_>,
+) -> Result<()> {
+    if ids.is_empty() {
+        return Ok(());
+    }
+    match write {
+        GuardedWrite::AddFlags(flags) => client.add_flags(mailbox, ids, flags)?,
+        GuardedWrite::RemoveFlags(flags) => client.delete_flags(mailbox, ids, flags)?,
+        GuardedWrite::Delete => {
+            for id in ids {
+                client.delete_message(mailbox, id)?;
+            }
+        }
+    }
+    Ok(())
+}
+
 /// Same as [`open`] plus any side-local bootstrap (e.g. m2dir store
 /// root + marker creation).
 pub fn init(config: SideConfig) -> Result<EmailClientStd> {
@@ -18,9 +18,10 @@
 //! Account configuration: each account pairs a `left` and a `right`
 //! [`SideConfig`] plus mailbox/message sync settings.
 
-use std::{collections::HashMap, fs, path::Path, path::PathBuf};
+use std::{collections::HashMap, fmt, fs, path::Path, path::PathBuf};
 
 use anyhow::{Context, Result};
+use io_email::client::EmailClientStd;
 use pimalaya_config::{
     secret::Secret,
     toml::{TomlConfig, shell_expanded_path, shell_expanded_string},
@@ -33,7 +34,7 @@ use pimalaya_stream::{
 };
 use serde::{Deserialize, Serialize};
 
-use crate::wizard;
+use crate::{client, wizard};
 
 /// Splices the per-side shared fields (`mailbox`, `flag`, `message`,
 /// `pool_size`) onto every protocol-specific config struct.
@@ -59,10 +60,10 @@ macro_rules! side_config {
             pub flag: FlagSidePermissions,
             #[serde(default)]
             pub message: MessageSidePermissions,
-            /// Per-side connection pool size override; defaults are
-            /// picked per backend.
+            /// Per-side connection pool size: `"auto"` (default) takes
+            /// the per-backend default, a number forces it.
             #[serde(default)]
-            pub pool_size: Option<usize>,
+            pub pool_size: PoolSize,
         }
     };
 }
@@ -118,6 +119,12 @@ impl Config {
         }
     }
 
+    /// Edits (or creates) `account_name` through the wizard, then
+    /// writes the config to `target`.
+    pub fn edit_account(self, target: &Path, account_name: &str) -> Result<Config> {
+        wizard::edit::edit_account(target, self, account_name)
+    }
+
     /// Serializes `self` to TOML at `path`, creating missing parent
     /// directories.
     pub fn write(&self, path: &Path) -> Result<()> {
@@ -153,6 +160,15 @@ pub struct AccountConfig {
     // TODO: message-level sync filters (date range, sender, subject).
     #[serde(default)]
     pub message: MessageSyncConfig,
+
+    /// Retry policy applied by the pool workers to hunks failing with
+    /// a transient error.
+    #[serde(default)]
+    pub retry: RetryConfig,
+
+    /// Shell commands run before and after the sync of the account.
+    #[serde(default)]
+    pub hooks: HooksConfig,
 }
 
 /// One side of the bidirectional sync; exactly one variant per side.
@@ -165,10 +181,22 @@ pub enum SideConfig {
 }
 
 impl SideConfig {
+    /// Opens the protocol client of the side. m2dir stores must
+    /// already exist, see [`SideConfig::init`].
+    pub fn open(self) -> Result<EmailClientStd> {
+        client::open(self)
+    }
+
+    /// Same as [`SideConfig::open`], creating the m2dir store first
+    /// when missing.
+    pub fn init(self) -> Result<EmailClientStd> {
+        client::init(self)
+    }
+
     side_accessor!(mailbox, MailboxSidePermissions);
     side_accessor!(flag, FlagSidePermissions);
     side_accessor!(message, MessageSidePermissions);
-    side_accessor!(pool_size, Option<usize>);
+    side_accessor!(pool_size, PoolSize);
 
     pub fn is_imap(&self) -> bool {
         matches!(self, Self::Imap(_))
@@ -188,6 +216,50 @@ impl SideConfig {
     }
 }
 
+/// Connection pool size of one side.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "RawPoolSize", into = "RawPoolSize")]
+pub enum PoolSize {
+    /// Fixed per-backend default (IMAP 8, JMAP 4, m2dir 8), minus the
+    /// connections the server refuses.
+    #[default]
+    Auto,
+    /// Explicit override, taken as is.
+    Fixed(usize),
+}
+
+/// TOML shape of [`PoolSize`]: either `"auto"` or a number.
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum RawPoolSize {
+    Fixed(usize),
+    Keyword(String),
+}
+
+impl TryFrom<RawPoolSize> for PoolSize {
+    type Error = String;
+
+    fn try_from(raw: RawPoolSize) -> Result<Self, Self::Error> {
+        match raw {
+            RawPoolSize::Fixed(0) => Err("pool size must be at least 1".into()),
+            RawPoolSize::Fixed(size) => Ok(Self::Fixed(size)),
+            RawPoolSize::Keyword(keyword) if keyword == "auto" => Ok(Self::Auto),
+            RawPoolSize::Keyword(keyword) => Err(format!(
+                "invalid pool size `{keyword}`, expected `auto` or a number"
+            )),
+        }
+    }
+}
+
+impl From<PoolSize> for RawPoolSize {
+    fn from(size: PoolSize) -> Self {
+        match size {
+            PoolSize::Auto => Self::Keyword("auto".into()),
+            PoolSize::Fixed(size) => Self::Fixed(size),
+        }
+    }
+}
+
 /// Per-side permission triple gating which sync hunks may materialize.
 #[derive(Clone, Copy, Debug)]
 pub struct SidePermissions {
@@ -213,6 +285,74 @@ pub struct MailboxSyncConfig {
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct MessageSyncConfig {}
 
+/// Per-hunk retry policy: exponential backoff between attempts,
+/// starting at `initial-delay-ms` and capped at `max-delay-ms`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct RetryConfig {
+    /// Total attempts per hunk, first one included; `1` disables
+    /// retries.
+    pub max_attempts: u32,
+    pub initial_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_delay_ms: 500,
+            max_delay_ms: 30_000,
+        }
+    }
+}
+
+/// Commands run around the sync, see [`crate::sync::hook`]. They only
+/// get the data of the run, never the account credentials.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct HooksConfig {
+    /// Run before anything is listed; a failure aborts the sync.
+    pub pre_sync: Option<HookCommand>,
+    /// Run once the snapshot is persisted, with the JSON report on
+    /// stdin.
+    pub post_sync: Option<HookCommand>,
+    /// Run once the snapshot is persisted, with one JSON line per
+    /// message copied to a side on stdin.
+    pub on_new_message: Option<HookCommand>,
+    /// Seconds a hook may run before it is killed and failed.
+    pub timeout_secs: u64,
+}
+
+impl Default for HooksConfig {
+    fn default() -> Self {
+        Self {
+            pre_sync: None,
+            post_sync: None,
+            on_new_message: None,
+            timeout_secs: 60,
+        }
+    }
+}
+
+/// Hook command: either a shell line run through `sh -c`, or a
+/// program followed by its arguments.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum HookCommand {
+    Shell(String),
+    Args(Vec<String>),
+}
+
+impl fmt::Display for HookCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Shell(line) => write!(f, "`{line}`"),
+            Self::Args(args) => write!(f, "`{}`", args.join(" ")),
+        }
+    }
+}
+
 /// Mailbox-name filter: include-list, exclude-list, or keep all.
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case", deny_unknown_fields)]
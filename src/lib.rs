@@ -0,0 +1,60 @@
+// This file is part of Neverest, a CLI to synchronize emails.
+//
+// Copyright (C) 2024-2026  soywod <pimalaya.org@posteo.net>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The Neverest sync engine, for tools embedding it instead of
+//! shelling out to the CLI.
+//!
+//! A sync takes an [`AccountConfig`] (usually read from the TOML
+//! [`Config`]), a [`Pool`] of connections to both sides and a
+//! [`SyncMode`]. [`run`] diffs both sides against the account
+//! [`CacheSnapshot`], applies the resulting hunks and returns a
+//! [`SyncReport`]. Stages, mailboxes, hunk outcomes and review prompts
+//! go through a [`SyncObserver`]:
+//!
+//! ```no_run
+//! use neverest::{Config, Pool, SyncMode, sync::observer::QuietObserver};
+//! use pimalaya_config::toml::TomlConfig;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let Some(mut config) = Config::from_paths_or_default(&[])? else {
+//!     anyhow::bail!("Cannot find config");
+//! };
+//! let Some((name, account)) = config.take_account(Some("work"))? else {
+//!     anyhow::bail!("Cannot find account");
+//! };
+//!
+//! let pool = Pool::open(account.left.clone(), account.right.clone())?;
+//! let report = neverest::run(&name, &account, pool, None, SyncMode::Apply, &mut QuietObserver)?;
+//! println!("{report}");
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The account must have been initialized first (`neverest init`), so
+//! that its cache snapshot exists.
+
+pub mod config;
+pub mod side;
+pub mod sync;
+
+pub(crate) mod client;
+pub(crate) mod wizard;
+
+pub use config::{AccountConfig, Config};
+pub use sync::{
+    SyncMode, cache::CacheSnapshot, observer::SyncObserver, pool::Pool, report::SyncReport, run,
+};
@@ -19,11 +19,6 @@
 //! the requested subcommand.
 
 mod cli;
-mod client;
-mod config;
-mod side;
-mod sync;
-mod wizard;
 
 use anyhow::Result;
 use clap::Parser;
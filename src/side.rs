@@ -19,13 +19,12 @@
 
 use std::fmt;
 
-use anyhow::{Result, bail};
 use io_email::client::EmailClientStd;
 use serde::{Deserialize, Serialize};
 
 /// Which half of the sync a value belongs to. Pure tag; carried by hunks
 /// and cache entries.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum Side {
     Left,
@@ -45,18 +44,20 @@ impl Side {
         }
     }
 
-    /// Returns the `(source, target)` client pair for a `Copy` hunk;
-    /// errors when `source == target`.
-    pub fn pair_mut<'a>(
-        source: Side,
-        target: Side,
-        left: &'a mut EmailClientStd,
-        right: &'a mut EmailClientStd,
-    ) -> Result<(&'a mut EmailClientStd, &'a mut EmailClientStd)> {
-        match (source, target) {
-            (Side::Left, Side::Right) => Ok((left, right)),
-            (Side::Right, Side::Left) => Ok((right, left)),
-            _ => bail!("Copy hunk has identical source and target side"),
+    /// The opposite side.
+    pub fn other(self) -> Side {
+        match self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
+    }
+
+    /// Single-side slice, for APIs listing the sides an operation
+    /// touches.
+    pub fn as_slice(self) -> &'static [Side] {
+        match self {
+            Side::Left => &[Side::Left],
+            Side::Right => &[Side::Right],
         }
     }
 }
@@ -40,6 +40,9 @@ pub type MailboxSnapshots = HashMap<String, MessageSnapshots>;
 /// [`crate::sync::diff::message_key`].
 pub type MessageSnapshots = HashMap<String, MessageEntry>;
 
+/// Per-message [`StatToken`]s keyed by backend id.
+pub type StatTokens = HashMap<String, StatToken>;
+
 /// Full snapshot loaded at sync start and saved at sync end.
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct CacheSnapshot {
@@ -56,6 +59,11 @@ pub struct CacheSnapshot {
     /// absent on backends without an account-global token.
     #[serde(default, with = "mailbox_states_serde")]
     pub mailbox_states: HashMap<Side, Vec<u8>>,
+
+    /// IMAP UIDVALIDITY per `(side, mailbox)` at the last listing; a
+    /// new value means every cached UID went stale.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub uid_validities: HashMap<Side, HashMap<String, u32>>,
 }
 
 impl CacheSnapshot {
@@ -122,6 +130,9 @@ impl CacheSnapshot {
         for state_map in self.states.values_mut() {
             state_map.remove(mailbox);
         }
+        for uid_validities in self.uid_validities.values_mut() {
+            uid_validities.remove(mailbox);
+        }
     }
 
     /// Opaque envelope-diff checkpoint for `(side, mailbox)`, or `None`
@@ -134,6 +145,24 @@ impl CacheSnapshot {
         self.states.entry(side).or_default().insert(mailbox, state);
     }
 
+    /// Drops the envelope-diff checkpoint of `(side, mailbox)`, e.g.
+    /// once its ids went stale.
+    pub fn clear_state(&mut self, side: Side, mailbox: &str) {
+        if let Some(states) = self.states.get_mut(&side) {
+            states.remove(mailbox);
+        }
+    }
+
+    /// UIDVALIDITY `(side, mailbox)` was last listed under, IMAP only.
+    pub fn uid_validity(&self, side: Side, mailbox: &str) -> Option<u32> {
+        self.uid_validities.get(&side)?.get(mailbox).copied()
+    }
+
+    pub fn set_uid_validity(&mut self, side: Side, mailbox: String, uid_validity: u32) {
+        let uid_validities = self.uid_validities.entry(side).or_default();
+        uid_validities.insert(mailbox, uid_validity);
+    }
+
     /// Opaque mailbox-set checkpoint for `side`, or `None` if a
     /// baseline still needs to be captured.
     pub fn mailbox_state(&self, side: Side) -> Option<&[u8]> {
@@ -151,6 +180,7 @@ impl CacheSnapshot {
             self.sides.clear();
             self.states.clear();
             self.mailbox_states.clear();
+            self.uid_validities.clear();
             return;
         }
         for mailbox in mailboxes {
@@ -162,7 +192,7 @@ impl CacheSnapshot {
     /// persists the snapshot.
     pub fn record(&mut self, mailbox_patch: &[PatchEntry<MailboxHunk>], path: &Path) -> Result<()> {
         for entry in mailbox_patch {
-            if entry.error.is_some() {
+            if entry.error.is_some() || entry.skipped.is_some() {
                 continue;
             }
             let MailboxHunk::Delete { mailbox, .. } = &entry.hunk else {
@@ -180,6 +210,19 @@ pub struct MessageEntry {
     pub id: String,
     #[serde(default)]
     pub flags: BTreeSet<Flag>,
+    /// Stat of the m2dir flags sidecar `flags` were read from; while
+    /// it matches, the sidecar is not read again.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<StatToken>,
+}
+
+/// Change token of a local file: any rewrite, in place or through a
+/// rename, moves at least one of these.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StatToken {
+    pub mtime_ns: u64,
+    pub size: u64,
+    pub inode: u64,
 }
 
 /// Serde adapter encoding `Vec<u8>` state blobs as base64 strings
@@ -279,6 +322,7 @@ mod tests {
         s.set_mailbox_state(Side::Left, vec![0xab, 0xcd, 0x00, 0x12]);
         s.set_mailbox_state(Side::Right, vec![]);
         s.set_messages(Side::Left, "INBOX".into(), MessageSnapshots::new());
+        s.set_uid_validity(Side::Left, "INBOX".into(), 42);
         s
     }
 
@@ -296,6 +340,7 @@ mod tests {
             parsed.state(Side::Right, "Sent"),
             Some([0xfe, 0xed].as_slice()),
         );
+        assert_eq!(parsed.uid_validity(Side::Left, "INBOX"), Some(42));
     }
 
     #[test]
@@ -321,6 +366,7 @@ mod tests {
 
         assert!(s.messages(Side::Left, "INBOX").is_none());
         assert!(s.state(Side::Left, "INBOX").is_none());
+        assert!(s.uid_validity(Side::Left, "INBOX").is_none());
         assert!(s.mailbox_state(Side::Left).is_some());
         assert!(s.state(Side::Left, "Archive").is_some());
         assert!(s.messages(Side::Left, "Archive").is_some());
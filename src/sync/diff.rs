@@ -23,6 +23,12 @@ use std::{
     collections::{BTreeSet, HashMap, HashSet, hash_map::DefaultHasher, hash_map::Entry},
     hash::{Hash, Hasher},
 };
+#[cfg(feature = "m2dir")]
+use std::{
+    fs,
+    path::Path,
+    time::{Duration, SystemTime},
+};
 
 #[cfg(feature = "m2dir")]
 use anyhow::{Context, Result};
@@ -43,23 +49,19 @@ use crate::{
     config::{MailboxFilter, SidePermissions},
     side::Side,
     sync::{
-        cache::{MessageEntry, MessageSnapshots},
-        hunk::{EmailHunk, MailboxHunk},
-        report::MessageCollision,
+        cache::{MessageEntry, MessageSnapshots, StatToken, StatTokens},
+        hunk::{EmailHunk, MailboxHunk, format_flag_list},
+        report::{Blocked, HunkReason, MessageCollision},
     },
 };
 
 /// 64-bit cross-side message identifier: hashed `Message-ID:` when
 /// present, falling back to `(subject, date, from)`.
 pub fn message_key(env: &Envelope) -> u64 {
-    let mut hasher = DefaultHasher::new();
     if let Some(message_id) = env.message_id.as_deref() {
-        // NOTE: tag so a Message-ID hash cannot collide with a
-        // fallback hash for a different message.
-        b"mid".hash(&mut hasher);
-        message_id.hash(&mut hasher);
-        return hasher.finish();
+        return message_id_key(message_id);
     }
+    let mut hasher = DefaultHasher::new();
     b"legacy".hash(&mut hasher);
     env.subject.hash(&mut hasher);
     if let Some(date) = env.date {
@@ -73,6 +75,16 @@ pub fn message_key(env: &Envelope) -> u64 {
     hasher.finish()
 }
 
+/// [`message_key`] of the messages carrying `message_id`.
+pub fn message_id_key(message_id: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    // NOTE: tag so a Message-ID hash cannot collide with a fallback
+    // hash for a different message.
+    b"mid".hash(&mut hasher);
+    message_id.hash(&mut hasher);
+    hasher.finish()
+}
+
 /// Live envelopes keyed by [`message_key`] content hash.
 pub type MessageMap<'a> = HashMap<u64, &'a Envelope>;
 
@@ -122,8 +134,9 @@ pub fn message_map<'a>(
 }
 
 /// Re-shapes an [`EnvelopePairs`] into the cache's
-/// [`MessageSnapshots`] layout.
-pub fn pairs_to_snapshot(pairs: &EnvelopePairs) -> MessageSnapshots {
+/// [`MessageSnapshots`] layout, attaching the sidecar `tokens` read
+/// along the way.
+pub fn pairs_to_snapshot(pairs: &EnvelopePairs, tokens: &StatTokens) -> MessageSnapshots {
     pairs
         .iter()
         .map(|(key, envelope)| {
@@ -132,12 +145,50 @@ pub fn pairs_to_snapshot(pairs: &EnvelopePairs) -> MessageSnapshots {
                 MessageEntry {
                     id: envelope.id.clone(),
                     flags: envelope.flags.clone(),
+                    token: tokens.get(&envelope.id).copied(),
                 },
             )
         })
         .collect()
 }
 
+/// Detects a mailbox identity reset on backends reporting no
+/// UIDVALIDITY: every message `pairs` shares (by content key) with
+/// `prev` now carries a different id.
+pub fn detect_identity_reset(prev: &MessageSnapshots, pairs: &EnvelopePairs) -> bool {
+    let mut matched = 0;
+    let mut rebound = 0;
+    for (key, envelope) in pairs {
+        let Some(entry) = prev.get(&key.to_string()) else {
+            continue;
+        };
+        matched += 1;
+        if entry.id != envelope.id {
+            rebound += 1;
+        }
+    }
+    matched > 0 && rebound == matched
+}
+
+/// Re-pairs `prev` with a listing taken after an identity reset: each
+/// cached entry takes the id its content key is now listed under, so
+/// the diff sees the same messages instead of deletes and copies.
+/// Entries missing from `pairs` keep their stale id. Returns how many
+/// ids were rewritten.
+pub fn repair_ids(prev: &mut MessageSnapshots, pairs: &EnvelopePairs) -> usize {
+    let mut rebound = 0;
+    for (key, envelope) in pairs {
+        let Some(entry) = prev.get_mut(&key.to_string()) else {
+            continue;
+        };
+        if entry.id != envelope.id {
+            entry.id = envelope.id.clone();
+            rebound += 1;
+        }
+    }
+    rebound
+}
+
 /// Synthesizes an [`EnvelopePairs`] from a prior snapshot plus the
 /// incremental delta (flag updates, new envelopes, vanished ids).
 pub fn pairs_from_delta(
@@ -187,11 +238,21 @@ fn stub_envelope(id: String, flags: BTreeSet<Flag>) -> Envelope {
     }
 }
 
+/// Files modified this close to the scan start get no token: a write
+/// landing in the same mtime tick later on would go unnoticed.
+#[cfg(feature = "m2dir")]
+const RACY_WINDOW: Duration = Duration::from_secs(2);
+
 /// Computes the envelope diff for an m2dir mailbox against `prev`.
 /// m2dir has no protocol checkpoint, so the cached snapshot is the
-/// authoritative baseline: we listdir once, read the sidecar flags
-/// for every entry, and only parse the RFC 5322 headers of entries
-/// that are not yet in `prev`.
+/// authoritative baseline: we listdir once, stat the sidecar of every
+/// entry and only read the flags of those whose [`StatToken`] moved,
+/// and only parse the RFC 5322 headers of entries that are not yet
+/// in `prev`.
+///
+/// `prev_state` is the mailbox directory token captured by the last
+/// scan; while it matches, the whole mailbox is skipped. The returned
+/// tokens cover every entry whose sidecar could be trusted.
 ///
 /// The parser is restricted to the five headers [`envelope_from`]
 /// actually consults (Subject, From, To, Date, Message-Id); all
@@ -201,15 +262,37 @@ pub fn diff_envelopes(
     client: &mut EmailClientStd,
     mailbox: &str,
     prev: Option<&MessageSnapshots>,
-) -> Result<EnvelopeDiff> {
-    let prev_by_id: HashMap<&str, &BTreeSet<Flag>> = prev
-        .map(|p| p.values().map(|e| (e.id.as_str(), &e.flags)).collect())
+    prev_state: Option<&[u8]>,
+) -> Result<(EnvelopeDiff, StatTokens)> {
+    let scan_start = SystemTime::now();
+    let prev_by_id: HashMap<&str, &MessageEntry> = prev
+        .map(|p| p.values().map(|e| (e.id.as_str(), e)).collect())
         .unwrap_or_default();
 
     let m2dir_client = client
         .as_m2dir_mut()
         .context("m2dir client not registered on this side")?;
     let m2dir = open_m2dir(m2dir_client, mailbox)?;
+    let dir_state = m2dir_state(m2dir.path(), scan_start);
+
+    // NOTE: m2dir writers add, remove and rewrite files through
+    // renames, which bump the mtime of the directory holding them.
+    if let (Some(prev), Some(prev_state), Some(state)) = (prev, prev_state, &dir_state) {
+        if prev_state == state.as_slice() {
+            let tokens = prev
+                .values()
+                .filter_map(|e| Some((e.id.clone(), e.token?)))
+                .collect();
+            let diff = EnvelopeDiff::Incremental {
+                new_state: state.clone(),
+                flag_updates: Vec::new(),
+                new_envelopes: Vec::new(),
+                vanished_ids: Vec::new(),
+            };
+            return Ok((diff, tokens));
+        }
+    }
+
     let entries = m2dir_client.list_entries(m2dir.clone())?;
 
     let parser = MessageParser::new()
@@ -223,18 +306,32 @@ pub fn diff_envelopes(
     let mut current_ids: HashSet<String> = HashSet::with_capacity(entries.len());
     let mut new_envelopes: Vec<Envelope> = Vec::new();
     let mut flag_updates: Vec<FlagUpdate> = Vec::new();
+    let mut tokens = StatTokens::with_capacity(entries.len());
 
     for entry in &entries {
         let id = entry.id().to_string();
         current_ids.insert(id.clone());
 
+        let sidecar = m2dir.path().join(".meta").join(format!("{id}.flags"));
+        let token = stat_token(&sidecar, scan_start);
+        if let Some(token) = token {
+            tokens.insert(id.clone(), token);
+        }
+
+        let prev_entry = prev_by_id.get(id.as_str());
+        if let (Some(prev_entry), Some(token)) = (prev_entry, token) {
+            if prev_entry.token == Some(token) {
+                continue;
+            }
+        }
+
         let flag_lines = m2dir_client.read_flags(&m2dir, entry.id())?;
         let current_flags: BTreeSet<Flag> = flag_lines
             .iter()
             .map(|line| Flag::from_raw(line.trim()))
             .collect();
 
-        match prev_by_id.get(id.as_str()) {
+        match prev_entry {
             None => {
                 let (_, bytes) = m2dir_client.get(m2dir.clone(), entry.id())?;
                 let parsed = parser
@@ -242,7 +339,7 @@ pub fn diff_envelopes(
                     .context("Parse m2dir message headers")?;
                 new_envelopes.push(envelope_from(entry, &flag_lines, &parsed));
             }
-            Some(prev_flags) if **prev_flags != current_flags => {
+            Some(prev_entry) if prev_entry.flags != current_flags => {
                 flag_updates.push(FlagUpdate {
                     id,
                     flags: current_flags,
@@ -258,14 +355,55 @@ pub fn diff_envelopes(
         .map(|id| id.to_string())
         .collect();
 
-    Ok(EnvelopeDiff::Incremental {
-        new_state: Vec::new(),
+    let diff = EnvelopeDiff::Incremental {
+        new_state: dir_state.unwrap_or_default(),
         flag_updates,
         new_envelopes,
         vanished_ids,
+    };
+    Ok((diff, tokens))
+}
+
+/// Stat of `path` as a [`StatToken`]; `None` when missing or touched
+/// within [`RACY_WINDOW`] of `scan_start`.
+#[cfg(feature = "m2dir")]
+fn stat_token(path: &Path, scan_start: SystemTime) -> Option<StatToken> {
+    let meta = fs::metadata(path).ok()?;
+    let mtime = meta.modified().ok()?;
+    if mtime + RACY_WINDOW > scan_start {
+        return None;
+    }
+
+    #[cfg(unix)]
+    let inode = std::os::unix::fs::MetadataExt::ino(&meta);
+    #[cfg(not(unix))]
+    let inode = 0;
+
+    Some(StatToken {
+        mtime_ns: mtime
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .ok()?
+            .as_nanos() as u64,
+        size: meta.len(),
+        inode,
     })
 }
 
+/// Directory-level token of an m2dir mailbox (the mailbox directory
+/// and its `.meta` sidecar directory), encoded as the opaque envelope
+/// checkpoint; `None` when either cannot be trusted yet.
+#[cfg(feature = "m2dir")]
+fn m2dir_state(path: &Path, scan_start: SystemTime) -> Option<Vec<u8>> {
+    let dir = stat_token(path, scan_start)?;
+    let meta_dir = path.join(".meta");
+    let meta = if meta_dir.exists() {
+        Some(stat_token(&meta_dir, scan_start)?)
+    } else {
+        None
+    };
+    serde_json::to_vec(&(dir, meta)).ok()
+}
+
 /// Applies a [`MailboxFilter`] to a freshly listed mailbox-name set.
 pub fn filter_mailboxes(all: &HashSet<String>, filter: &MailboxFilter) -> HashSet<String> {
     match filter {
@@ -284,7 +422,8 @@ pub fn filter_mailboxes(all: &HashSet<String>, filter: &MailboxFilter) -> HashSe
 }
 
 /// Mailbox-level three-way diff: classifies asymmetries via the
-/// cached snapshot's last-known mailbox set per side.
+/// cached snapshot's last-known mailbox set per side. Hunks the side
+/// permissions forbid go to `blocked` instead.
 pub fn diff_mailboxes(
     left: &HashSet<String>,
     right: &HashSet<String>,
@@ -292,44 +431,70 @@ pub fn diff_mailboxes(
     prev_right: &HashSet<String>,
     left_perms: SidePermissions,
     right_perms: SidePermissions,
+    blocked: &mut Vec<Blocked<MailboxHunk>>,
 ) -> Vec<MailboxHunk> {
     let mut hunks = Vec::new();
     for name in left.difference(right) {
         if prev_right.contains(name) {
-            if left_perms.mailbox.delete {
-                hunks.push(MailboxHunk::Delete {
-                    side: Side::Left,
-                    mailbox: name.clone(),
-                });
-            }
-        } else if right_perms.mailbox.create {
-            hunks.push(MailboxHunk::Create {
+            let hunk = MailboxHunk::Delete {
+                side: Side::Left,
+                mailbox: name.clone(),
+            };
+            let allowed = left_perms.mailbox.delete;
+            hunks.extend(gate(blocked, hunk, allowed, Side::Left, "mailbox.delete"));
+        } else {
+            let hunk = MailboxHunk::Create {
                 side: Side::Right,
                 mailbox: name.clone(),
-            });
+            };
+            let allowed = right_perms.mailbox.create;
+            hunks.extend(gate(blocked, hunk, allowed, Side::Right, "mailbox.create"));
         }
     }
     for name in right.difference(left) {
         if prev_left.contains(name) {
-            if right_perms.mailbox.delete {
-                hunks.push(MailboxHunk::Delete {
-                    side: Side::Right,
-                    mailbox: name.clone(),
-                });
-            }
-        } else if left_perms.mailbox.create {
-            hunks.push(MailboxHunk::Create {
+            let hunk = MailboxHunk::Delete {
+                side: Side::Right,
+                mailbox: name.clone(),
+            };
+            let allowed = right_perms.mailbox.delete;
+            hunks.extend(gate(blocked, hunk, allowed, Side::Right, "mailbox.delete"));
+        } else {
+            let hunk = MailboxHunk::Create {
                 side: Side::Left,
                 mailbox: name.clone(),
-            });
+            };
+            let allowed = left_perms.mailbox.create;
+            hunks.extend(gate(blocked, hunk, allowed, Side::Left, "mailbox.create"));
         }
     }
     hunks
 }
 
+/// Returns `hunk` when `allowed`, records it in `blocked` under the
+/// `side` permission that forbids it otherwise.
+fn gate<H>(
+    blocked: &mut Vec<Blocked<H>>,
+    hunk: H,
+    allowed: bool,
+    side: Side,
+    permission: &str,
+) -> Option<H> {
+    if allowed {
+        return Some(hunk);
+    }
+    blocked.push(Blocked {
+        hunk,
+        permission: format!("{side}.{permission}"),
+    });
+    None
+}
+
 /// Message-level three-way diff for one mailbox; emits
 /// `Copy`/`Delete` and delegates flag-only divergences to
-/// [`diff_flags`].
+/// [`diff_flags`]. Hunks the side permissions forbid go to `blocked`
+/// instead.
+#[allow(clippy::too_many_arguments)]
 pub fn diff_messages(
     mailbox: &str,
     left: &MessageMap<'_>,
@@ -338,6 +503,7 @@ pub fn diff_messages(
     prev_right: &MessageSnapshots,
     left_perms: SidePermissions,
     right_perms: SidePermissions,
+    blocked: &mut Vec<Blocked<EmailHunk>>,
 ) -> Vec<EmailHunk> {
     let mut hunks = Vec::new();
 
@@ -354,27 +520,31 @@ pub fn diff_messages(
                     prev_right.get(&key_str),
                     left_perms,
                     right_perms,
+                    blocked,
                 ));
             }
             None => {
                 if prev_right.contains_key(&key_str) {
-                    if left_perms.message.delete {
-                        hunks.push(EmailHunk::Delete {
-                            side: Side::Left,
-                            mailbox: mailbox.to_string(),
-                            id: m.id.clone(),
-                            content_key: *key,
-                        });
-                    }
-                } else if right_perms.message.create {
-                    hunks.push(EmailHunk::Copy {
+                    let hunk = EmailHunk::Delete {
+                        side: Side::Left,
+                        mailbox: mailbox.to_string(),
+                        id: m.id.clone(),
+                        content_key: *key,
+                    };
+                    let allowed = left_perms.message.delete;
+                    hunks.extend(gate(blocked, hunk, allowed, Side::Left, "message.delete"));
+                } else {
+                    let hunk = EmailHunk::Copy {
                         source_side: Side::Left,
                         target_side: Side::Right,
                         mailbox: mailbox.to_string(),
                         source_id: m.id.clone(),
                         flags: m.flags.clone(),
+                        size: m.size,
                         content_key: *key,
-                    });
+                    };
+                    let allowed = right_perms.message.create;
+                    hunks.extend(gate(blocked, hunk, allowed, Side::Right, "message.create"));
                 }
             }
         }
@@ -386,23 +556,26 @@ pub fn diff_messages(
         }
         let key_str = key.to_string();
         if prev_left.contains_key(&key_str) {
-            if right_perms.message.delete {
-                hunks.push(EmailHunk::Delete {
-                    side: Side::Right,
-                    mailbox: mailbox.to_string(),
-                    id: m.id.clone(),
-                    content_key: *key,
-                });
-            }
-        } else if left_perms.message.create {
-            hunks.push(EmailHunk::Copy {
+            let hunk = EmailHunk::Delete {
+                side: Side::Right,
+                mailbox: mailbox.to_string(),
+                id: m.id.clone(),
+                content_key: *key,
+            };
+            let allowed = right_perms.message.delete;
+            hunks.extend(gate(blocked, hunk, allowed, Side::Right, "message.delete"));
+        } else {
+            let hunk = EmailHunk::Copy {
                 source_side: Side::Right,
                 target_side: Side::Left,
                 mailbox: mailbox.to_string(),
                 source_id: m.id.clone(),
                 flags: m.flags.clone(),
+                size: m.size,
                 content_key: *key,
-            });
+            };
+            let allowed = left_perms.message.create;
+            hunks.extend(gate(blocked, hunk, allowed, Side::Left, "message.create"));
         }
     }
 
@@ -411,6 +584,8 @@ pub fn diff_messages(
 
 /// Flag-level diff for a pair of messages present on both sides;
 /// `\Deleted` is treated as a delete-message verb rather than a flag.
+/// Hunks the side permissions forbid go to `blocked` instead.
+#[allow(clippy::too_many_arguments)]
 pub fn diff_flags(
     mailbox: &str,
     content_key: u64,
@@ -420,6 +595,7 @@ pub fn diff_flags(
     prev_right: Option<&MessageEntry>,
     left_perms: SidePermissions,
     right_perms: SidePermissions,
+    blocked: &mut Vec<Blocked<EmailHunk>>,
 ) -> Vec<EmailHunk> {
     let mut hunks = Vec::new();
 
@@ -452,21 +628,25 @@ pub fn diff_flags(
         .cloned()
         .collect();
 
-    if left_deleted_seen && !right_deleted_seen && left_perms.message.delete {
-        hunks.push(EmailHunk::Delete {
+    if left_deleted_seen && !right_deleted_seen {
+        let hunk = EmailHunk::Delete {
             side: Side::Left,
             mailbox: mailbox.to_string(),
             id: left.id.clone(),
             content_key,
-        });
+        };
+        let allowed = left_perms.message.delete;
+        hunks.extend(gate(blocked, hunk, allowed, Side::Left, "message.delete"));
     }
-    if right_deleted_seen && !left_deleted_seen && right_perms.message.delete {
-        hunks.push(EmailHunk::Delete {
+    if right_deleted_seen && !left_deleted_seen {
+        let hunk = EmailHunk::Delete {
             side: Side::Right,
             mailbox: mailbox.to_string(),
             id: right.id.clone(),
             content_key,
-        });
+        };
+        let allowed = right_perms.message.delete;
+        hunks.extend(gate(blocked, hunk, allowed, Side::Right, "message.delete"));
     }
 
     let prev_left_flags: BTreeSet<Flag> = prev_left
@@ -508,45 +688,152 @@ pub fn diff_flags(
         }
     }
 
-    if !to_add_right.is_empty() && right_perms.flag.update {
-        hunks.push(EmailHunk::AddFlags {
+    if !to_add_right.is_empty() {
+        let hunk = EmailHunk::AddFlags {
             side: Side::Right,
             mailbox: mailbox.to_string(),
             id: right.id.clone(),
             flags: to_add_right,
             content_key,
-        });
+        };
+        let allowed = right_perms.flag.update;
+        hunks.extend(gate(blocked, hunk, allowed, Side::Right, "flag.update"));
     }
-    if !to_add_left.is_empty() && left_perms.flag.update {
-        hunks.push(EmailHunk::AddFlags {
+    if !to_add_left.is_empty() {
+        let hunk = EmailHunk::AddFlags {
             side: Side::Left,
             mailbox: mailbox.to_string(),
             id: left.id.clone(),
             flags: to_add_left,
             content_key,
-        });
+        };
+        let allowed = left_perms.flag.update;
+        hunks.extend(gate(blocked, hunk, allowed, Side::Left, "flag.update"));
     }
-    if !to_remove_right.is_empty() && right_perms.flag.update {
-        hunks.push(EmailHunk::RemoveFlags {
+    if !to_remove_right.is_empty() {
+        let hunk = EmailHunk::RemoveFlags {
             side: Side::Right,
             mailbox: mailbox.to_string(),
             id: right.id.clone(),
             flags: to_remove_right,
             content_key,
-        });
+        };
+        let allowed = right_perms.flag.update;
+        hunks.extend(gate(blocked, hunk, allowed, Side::Right, "flag.update"));
     }
-    if !to_remove_left.is_empty() && left_perms.flag.update {
-        hunks.push(EmailHunk::RemoveFlags {
+    if !to_remove_left.is_empty() {
+        let hunk = EmailHunk::RemoveFlags {
             side: Side::Left,
             mailbox: mailbox.to_string(),
             id: left.id.clone(),
             flags: to_remove_left,
             content_key,
-        });
+        };
+        let allowed = left_perms.flag.update;
+        hunks.extend(gate(blocked, hunk, allowed, Side::Left, "flag.update"));
     }
     hunks
 }
 
+/// Rebuilds why [`diff_mailboxes`] emitted `hunk`; the verdict
+/// follows from the hunk alone.
+pub fn explain_mailbox(hunk: &MailboxHunk) -> HunkReason {
+    match hunk {
+        MailboxHunk::Create { side, .. } => {
+            let origin = side.other();
+            HunkReason {
+                summary: format!("absent on {side}, not in {side} snapshot → new on {origin}"),
+                evidence: vec![
+                    format!("{origin}: listed"),
+                    format!("{side}: not listed"),
+                    format!("{side} snapshot: no entry"),
+                ],
+            }
+        }
+        MailboxHunk::Delete { side, .. } => {
+            let origin = side.other();
+            HunkReason {
+                summary: format!("absent on {origin}, in {origin} snapshot → deleted on {origin}"),
+                evidence: vec![
+                    format!("{side}: listed"),
+                    format!("{origin}: not listed"),
+                    format!("{origin} snapshot: listed"),
+                ],
+            }
+        }
+    }
+}
+
+/// Rebuilds why [`diff_messages`] or [`diff_flags`] emitted `hunk`,
+/// from the live envelopes and cached entries of its message.
+pub fn explain_message(
+    hunk: &EmailHunk,
+    left: Option<&Envelope>,
+    right: Option<&Envelope>,
+    prev_left: Option<&MessageEntry>,
+    prev_right: Option<&MessageEntry>,
+) -> HunkReason {
+    let listed = |side: Side| match side {
+        Side::Left => left,
+        Side::Right => right,
+    };
+    let cached = |side: Side| match side {
+        Side::Left => prev_left,
+        Side::Right => prev_right,
+    };
+
+    let summary = match hunk {
+        EmailHunk::Copy {
+            source_side,
+            target_side,
+            ..
+        } => {
+            format!("absent on {target_side}, not in {target_side} snapshot → new on {source_side}")
+        }
+        EmailHunk::Delete { side, .. } if listed(side.other()).is_some() => {
+            format!("flagged \\deleted on {side} only → removed from {side}")
+        }
+        EmailHunk::Delete { side, .. } => {
+            let origin = side.other();
+            format!("absent on {origin}, in {origin} snapshot → deleted on {origin}")
+        }
+        EmailHunk::AddFlags { side, flags, .. } => {
+            let origin = side.other();
+            let flags = format_flag_list(flags);
+            if prev_left.is_none() && prev_right.is_none() {
+                format!("{flags} on {origin} only, no baseline yet → merged into {side}")
+            } else {
+                format!("{flags} added on {origin} since last sync")
+            }
+        }
+        EmailHunk::RemoveFlags { side, flags, .. } => {
+            let origin = side.other();
+            let flags = format_flag_list(flags);
+            format!("{flags} removed on {origin} since last sync")
+        }
+    };
+
+    let mut evidence = Vec::with_capacity(4);
+    for side in [Side::Left, Side::Right] {
+        evidence.push(match listed(side) {
+            Some(env) => format!("{side}: `{}` {}", env.id, format_flag_list(&env.flags)),
+            None => format!("{side}: not listed"),
+        });
+    }
+    for side in [Side::Left, Side::Right] {
+        evidence.push(match cached(side) {
+            Some(entry) => format!(
+                "{side} snapshot: `{}` {}",
+                entry.id,
+                format_flag_list(&entry.flags)
+            ),
+            None => format!("{side} snapshot: no entry"),
+        });
+    }
+
+    HunkReason { summary, evidence }
+}
+
 #[cfg(test)]
 mod tests {
     use io_email::flag::{Flag, IanaFlag};
@@ -604,6 +891,7 @@ mod tests {
         MessageEntry {
             id: id.to_string(),
             flags: flags.iter().cloned().collect(),
+            token: None,
         }
     }
 
@@ -640,6 +928,7 @@ mod tests {
             &prev_right,
             perms_all(),
             perms_all(),
+            &mut Vec::new(),
         );
         assert!(hunks.is_empty());
     }
@@ -657,6 +946,7 @@ mod tests {
             &prev_right,
             perms_all(),
             perms_all(),
+            &mut Vec::new(),
         );
         assert_eq!(hunks.len(), 1);
         assert!(matches!(
@@ -678,6 +968,7 @@ mod tests {
             &prev_right,
             perms_all(),
             perms_all(),
+            &mut Vec::new(),
         );
         assert_eq!(hunks.len(), 1);
         assert!(matches!(
@@ -699,6 +990,7 @@ mod tests {
             &prev_right,
             perms_all(),
             perms_all(),
+            &mut Vec::new(),
         );
         assert_eq!(hunks.len(), 1);
         assert!(matches!(
@@ -720,6 +1012,7 @@ mod tests {
             &prev_right,
             perms_all(),
             perms_all(),
+            &mut Vec::new(),
         );
         assert_eq!(hunks.len(), 1);
         assert!(matches!(
@@ -745,6 +1038,7 @@ mod tests {
                 delete: true,
             },
         );
+        let mut blocked = Vec::new();
         let hunks = diff_mailboxes(
             &left,
             &right,
@@ -752,8 +1046,15 @@ mod tests {
             &prev_right,
             left_perms,
             perms_all(),
+            &mut blocked,
         );
         assert!(hunks.is_empty());
+        assert_eq!(blocked.len(), 1);
+        assert_eq!(blocked[0].permission, "left.mailbox.delete");
+        assert!(matches!(
+            &blocked[0].hunk,
+            MailboxHunk::Delete { side: Side::Left, mailbox } if mailbox == "INBOX"
+        ));
     }
 
     #[test]
@@ -778,6 +1079,7 @@ mod tests {
             &prev_right,
             perms_all(),
             perms_all(),
+            &mut Vec::new(),
         );
         assert!(hunks.is_empty());
     }
@@ -802,6 +1104,7 @@ mod tests {
             &prev_right,
             perms_all(),
             perms_all(),
+            &mut Vec::new(),
         );
         assert_eq!(hunks.len(), 1);
         assert!(matches!(
@@ -832,6 +1135,7 @@ mod tests {
             &MessageSnapshots::new(),
             perms_all(),
             perms_all(),
+            &mut Vec::new(),
         );
         assert_eq!(hunks.len(), 1);
         assert!(matches!(
@@ -863,6 +1167,7 @@ mod tests {
             &MessageSnapshots::new(),
             perms_all(),
             perms_all(),
+            &mut Vec::new(),
         );
         assert_eq!(hunks.len(), 1);
         assert!(matches!(
@@ -895,6 +1200,7 @@ mod tests {
             },
         );
 
+        let mut blocked = Vec::new();
         let hunks = diff_messages(
             "INBOX",
             &left,
@@ -903,8 +1209,12 @@ mod tests {
             &MessageSnapshots::new(),
             perms_all(),
             right_perms,
+            &mut blocked,
         );
         assert!(hunks.is_empty());
+        assert_eq!(blocked.len(), 1);
+        assert_eq!(blocked[0].permission, "right.message.create");
+        assert!(matches!(&blocked[0].hunk, EmailHunk::Copy { source_id, .. } if source_id == "L1"));
     }
 
     #[test]
@@ -962,6 +1272,7 @@ mod tests {
             &MessageSnapshots::new(),
             perms_all(),
             perms_all(),
+            &mut Vec::new(),
         );
         assert_eq!(hunks.len(), 2);
         assert_eq!(collisions.len(), 1);
@@ -992,6 +1303,7 @@ mod tests {
             Some(&entry("R1", &[])),
             perms_all(),
             perms_all(),
+            &mut Vec::new(),
         );
         assert_eq!(hunks.len(), 1);
         assert!(matches!(
@@ -1016,6 +1328,7 @@ mod tests {
             Some(&entry("R1", &[seen.clone()])),
             perms_all(),
             perms_all(),
+            &mut Vec::new(),
         );
         assert_eq!(hunks.len(), 1);
         assert!(matches!(
@@ -1040,6 +1353,7 @@ mod tests {
             None,
             perms_all(),
             perms_all(),
+            &mut Vec::new(),
         );
         assert_eq!(hunks.len(), 1);
         assert!(matches!(
@@ -1064,6 +1378,7 @@ mod tests {
             None,
             perms_all(),
             perms_all(),
+            &mut Vec::new(),
         );
         assert_eq!(hunks.len(), 1);
         assert!(matches!(
@@ -1088,10 +1403,95 @@ mod tests {
             None,
             perms_all(),
             perms_all(),
+            &mut Vec::new(),
         );
         assert!(hunks.is_empty());
     }
 
+    #[test]
+    fn explain_message_names_the_side_that_changed() {
+        let seen = Flag::from_iana(IanaFlag::Seen);
+        let left = envelope("L1", Some("<a>"), &[seen.clone()]);
+        let right = envelope("R1", Some("<a>"), &[]);
+        let prev_left = entry("L1", &[seen.clone()]);
+        let prev_right = entry("R1", &[seen.clone()]);
+
+        let hunks = diff_flags(
+            "INBOX",
+            42,
+            &left,
+            &right,
+            Some(&prev_left),
+            Some(&prev_right),
+            perms_all(),
+            perms_all(),
+            &mut Vec::new(),
+        );
+        let reason = explain_message(
+            &hunks[0],
+            Some(&left),
+            Some(&right),
+            Some(&prev_left),
+            Some(&prev_right),
+        );
+        assert_eq!(reason.summary, "[\\seen] removed on right since last sync");
+        assert_eq!(reason.evidence[1], "right: `R1` []");
+
+        let copy = EmailHunk::Copy {
+            source_side: Side::Left,
+            target_side: Side::Right,
+            mailbox: "INBOX".into(),
+            source_id: "L1".into(),
+            flags: BTreeSet::new(),
+            size: 0,
+            content_key: 42,
+        };
+        let reason = explain_message(&copy, Some(&left), None, None, None);
+        assert_eq!(
+            reason.summary,
+            "absent on right, not in right snapshot → new on left"
+        );
+        assert_eq!(reason.evidence[3], "right snapshot: no entry");
+    }
+
+    #[test]
+    fn identity_reset_detected_when_every_shared_id_changed() {
+        let prev: MessageSnapshots = [("1", "10"), ("2", "11")]
+            .into_iter()
+            .map(|(key, id)| (key.to_string(), entry(id, &[])))
+            .collect();
+        let renumbered: EnvelopePairs = vec![
+            (1, envelope("100", None, &[])),
+            (2, envelope("101", None, &[])),
+            (3, envelope("102", None, &[])),
+        ];
+        assert!(detect_identity_reset(&prev, &renumbered));
+
+        let partly: EnvelopePairs = vec![
+            (1, envelope("10", None, &[])),
+            (2, envelope("101", None, &[])),
+        ];
+        assert!(!detect_identity_reset(&prev, &partly));
+        assert!(!detect_identity_reset(&prev, &Vec::new()));
+    }
+
+    #[test]
+    fn repair_rebinds_listed_ids_and_keeps_missing_ones() {
+        let mut prev: MessageSnapshots = [("1", "10"), ("2", "11")]
+            .into_iter()
+            .map(|(key, id)| (key.to_string(), entry(id, &[])))
+            .collect();
+        let renumbered: EnvelopePairs = vec![
+            (1, envelope("100", None, &[])),
+            (3, envelope("102", None, &[])),
+        ];
+
+        assert_eq!(repair_ids(&mut prev, &renumbered), 1);
+        assert_eq!(prev["1"].id, "100");
+        assert_eq!(prev["2"].id, "11");
+        assert!(!prev.contains_key("3"));
+    }
+
     #[cfg(feature = "m2dir")]
     mod m2dir {
         use io_email::{
@@ -1113,6 +1513,7 @@ mod tests {
                     MessageEntry {
                         id: (*id).to_string(),
                         flags: flags.iter().cloned().collect(),
+                        token: None,
                     },
                 );
             }
@@ -1143,7 +1544,7 @@ mod tests {
             let mut client = mk_client(dir.path());
             client.create_mailbox("inbox").unwrap();
 
-            let diff = diff_envelopes(&mut client, "inbox", None).unwrap();
+            let (diff, _) = diff_envelopes(&mut client, "inbox", None, None).unwrap();
             match diff {
                 EnvelopeDiff::Incremental {
                     new_envelopes,
@@ -1167,7 +1568,7 @@ mod tests {
             let _id_a = client.add_message("inbox", &[], RAW_A.to_vec()).unwrap();
             let _id_b = client.add_message("inbox", &[], RAW_B.to_vec()).unwrap();
 
-            let diff = diff_envelopes(&mut client, "inbox", None).unwrap();
+            let (diff, _) = diff_envelopes(&mut client, "inbox", None, None).unwrap();
             let EnvelopeDiff::Incremental { new_envelopes, .. } = diff else {
                 panic!("expected Incremental");
             };
@@ -1189,7 +1590,7 @@ mod tests {
             let id_b = client.add_message("inbox", &[], RAW_B.to_vec()).unwrap();
 
             let prev = snapshot_with(&[(&id_a, &[]), (&id_b, &[])]);
-            let diff = diff_envelopes(&mut client, "inbox", Some(&prev)).unwrap();
+            let (diff, _) = diff_envelopes(&mut client, "inbox", Some(&prev), None).unwrap();
             let EnvelopeDiff::Incremental {
                 new_envelopes,
                 flag_updates,
@@ -1221,7 +1622,7 @@ mod tests {
                 )
                 .unwrap();
 
-            let diff = diff_envelopes(&mut client, "inbox", Some(&prev)).unwrap();
+            let (diff, _) = diff_envelopes(&mut client, "inbox", Some(&prev), None).unwrap();
             let EnvelopeDiff::Incremental {
                 new_envelopes,
                 flag_updates,
@@ -1249,7 +1650,7 @@ mod tests {
             let prev = snapshot_with(&[(&id_a, &[]), (&id_b, &[])]);
             client.delete_message("inbox", &id_b).unwrap();
 
-            let diff = diff_envelopes(&mut client, "inbox", Some(&prev)).unwrap();
+            let (diff, _) = diff_envelopes(&mut client, "inbox", Some(&prev), None).unwrap();
             let EnvelopeDiff::Incremental {
                 new_envelopes,
                 flag_updates,
@@ -1308,6 +1709,7 @@ mod tests {
                     mailbox: "inbox".into(),
                     source_id: "irrelevant".into(),
                     flags: flags.iter().cloned().collect(),
+                    size: 0,
                     content_key: key,
                 };
                 match &hunk {
@@ -1319,11 +1721,12 @@ mod tests {
                     MessageEntry {
                         id: new_id,
                         flags: flags.iter().cloned().collect(),
+                        token: None,
                     },
                 );
             }
 
-            let diff = diff_envelopes(&mut client, "inbox", Some(&snapshot)).unwrap();
+            let (diff, _) = diff_envelopes(&mut client, "inbox", Some(&snapshot), None).unwrap();
             let EnvelopeDiff::Incremental {
                 new_envelopes,
                 flag_updates,
@@ -1347,6 +1750,47 @@ mod tests {
             );
         }
 
+        #[test]
+        fn unchanged_sidecar_token_skips_flag_read() {
+            let dir = tempdir().unwrap();
+            let mut client = mk_client(dir.path());
+            client.create_mailbox("inbox").unwrap();
+            let seen = Flag::from_iana(IanaFlag::Seen);
+            let id_a = client
+                .add_message("inbox", &[seen.clone()], RAW_A.to_vec())
+                .unwrap();
+
+            let m2dir = open_m2dir(client.as_m2dir_mut().unwrap(), "inbox").unwrap();
+            let sidecar = m2dir.path().join(".meta").join(format!("{id_a}.flags"));
+            let an_hour_ago = SystemTime::now() - Duration::from_secs(3600);
+            fs::File::options()
+                .write(true)
+                .open(&sidecar)
+                .unwrap()
+                .set_modified(an_hour_ago)
+                .unwrap();
+
+            let prev = snapshot_with(&[(&id_a, &[])]);
+            let (diff, tokens) = diff_envelopes(&mut client, "inbox", Some(&prev), None).unwrap();
+            let EnvelopeDiff::Incremental { flag_updates, .. } = diff else {
+                panic!("expected Incremental");
+            };
+            assert_eq!(flag_updates.len(), 1);
+            assert!(tokens.contains_key(&id_a));
+
+            // NOTE: stale flags paired with the current token are
+            // trusted, proving the sidecar was not read again.
+            let mut prev = snapshot_with(&[(&id_a, &[])]);
+            for entry in prev.values_mut() {
+                entry.token = tokens.get(&entry.id).copied();
+            }
+            let (diff, _) = diff_envelopes(&mut client, "inbox", Some(&prev), None).unwrap();
+            let EnvelopeDiff::Incremental { flag_updates, .. } = diff else {
+                panic!("expected Incremental");
+            };
+            assert!(flag_updates.is_empty());
+        }
+
         #[test]
         fn new_message_added_after_snapshot_is_parsed_once() {
             let dir = tempdir().unwrap();
@@ -1357,7 +1801,7 @@ mod tests {
             let prev = snapshot_with(&[(&id_a, &[])]);
             let id_b = client.add_message("inbox", &[], RAW_B.to_vec()).unwrap();
 
-            let diff = diff_envelopes(&mut client, "inbox", Some(&prev)).unwrap();
+            let (diff, _) = diff_envelopes(&mut client, "inbox", Some(&prev), None).unwrap();
             let EnvelopeDiff::Incremental { new_envelopes, .. } = diff else {
                 panic!("expected Incremental");
             };
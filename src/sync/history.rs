@@ -0,0 +1,440 @@
+// This file is part of Neverest, a CLI to synchronize emails.
+//
+// Copyright (C) 2024-2026  soywod <pimalaya.org@posteo.net>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Per-account run history: every [`SyncReport`] is appended as one
+//! JSON line next to the cache snapshot, rotated by size, and queried
+//! by `neverest log`.
+
+use std::{
+    fmt,
+    fs::{self, OpenOptions},
+    io::{BufRead, BufReader, ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Local};
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::sync::{
+    cache::CacheSnapshot,
+    diff::message_id_key,
+    hunk::EmailHunk,
+    report::{MessageDetails, PatchEntry, SyncReport},
+    review::Reviewable,
+};
+
+/// Size (bytes) past which the history file is rotated.
+const HISTORY_MAX_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Rotated files kept (`history.1.jsonl` being the most recent).
+const HISTORY_ROTATIONS: usize = 3;
+
+/// One line of the history file.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct HistoryRecord {
+    pub time: DateTime<Local>,
+    pub account: String,
+    pub report: SyncReport,
+}
+
+/// Borrowed [`HistoryRecord`], to append a report without cloning it.
+#[derive(Serialize)]
+struct HistoryRecordRef<'a> {
+    time: DateTime<Local>,
+    account: &'a str,
+    report: &'a SyncReport,
+}
+
+/// History file of `account`, next to its cache snapshot.
+pub fn path(account: &str) -> Result<PathBuf> {
+    Ok(CacheSnapshot::path(account)?.with_file_name("history.jsonl"))
+}
+
+/// Appends `report` as a new line of the history at `path`, rotating
+/// the file first once it grew past `HISTORY_MAX_BYTES`.
+pub fn append(path: &Path, report: &SyncReport) -> Result<()> {
+    let record = HistoryRecordRef {
+        time: Local::now(),
+        account: &report.account,
+        report,
+    };
+    let mut line = serde_json::to_vec(&record).context("Serialize history record error")?;
+    line.push(b'\n');
+
+    match fs::metadata(path) {
+        Ok(meta) if meta.len() >= HISTORY_MAX_BYTES => rotate(path)?,
+        Ok(_) => {}
+        Err(err) if err.kind() == ErrorKind::NotFound => {}
+        Err(err) => bail!("Read history `{}` metadata error: {err}", path.display()),
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).context(format!("Create history dir `{}` error", dir.display()))?;
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .context(format!("Open history `{}` error", path.display()))?;
+    file.write_all(&line)
+        .context(format!("Write history `{}` error", path.display()))?;
+    Ok(())
+}
+
+/// Shifts `history.jsonl` to `history.1.jsonl`, and so on, dropping
+/// the oldest rotation.
+fn rotate(path: &Path) -> Result<()> {
+    for n in (1..HISTORY_ROTATIONS).rev() {
+        let from = rotated(path, n);
+        if from.exists() {
+            let to = rotated(path, n + 1);
+            fs::rename(&from, &to).context(format!("Rotate history `{}` error", from.display()))?;
+        }
+    }
+    let to = rotated(path, 1);
+    fs::rename(path, &to).context(format!("Rotate history `{}` error", path.display()))?;
+    Ok(())
+}
+
+fn rotated(path: &Path, n: usize) -> PathBuf {
+    path.with_extension(format!("{n}.jsonl"))
+}
+
+/// Every record of the history at `path`, rotations included, oldest
+/// first. Unreadable lines are skipped with a warning.
+pub fn load(path: &Path) -> Result<Vec<HistoryRecord>> {
+    let files = (1..=HISTORY_ROTATIONS)
+        .rev()
+        .map(|n| rotated(path, n))
+        .chain([path.to_owned()]);
+
+    let mut records = Vec::new();
+    for file in files {
+        let reader = match fs::File::open(&file) {
+            Ok(reader) => BufReader::new(reader),
+            Err(err) if err.kind() == ErrorKind::NotFound => continue,
+            Err(err) => bail!("Open history `{}` error: {err}", file.display()),
+        };
+        for (n, line) in reader.lines().enumerate() {
+            let line = line.context(format!("Read history `{}` error", file.display()))?;
+            match serde_json::from_str(&line) {
+                Ok(record) => records.push(record),
+                Err(err) => warn!("skip line {} of `{}`: {err}", n + 1, file.display()),
+            }
+        }
+    }
+    Ok(records)
+}
+
+/// What `neverest log` keeps of the history.
+#[derive(Clone, Debug, Default)]
+pub struct HistoryFilter {
+    /// Only hunks about this mailbox (ASCII case-insensitive).
+    pub mailbox: Option<String>,
+    /// Only hunks about the message carrying this `Message-ID:`, with
+    /// or without its angle brackets.
+    pub message_id: Option<String>,
+    /// Only runs recorded at or after this time.
+    pub since: Option<DateTime<Local>>,
+}
+
+impl HistoryFilter {
+    fn narrows_hunks(&self) -> bool {
+        self.mailbox.is_some() || self.message_id.is_some()
+    }
+
+    /// Content keys the message id filter may have been hashed to.
+    fn message_keys(&self) -> Vec<u64> {
+        let Some(message_id) = &self.message_id else {
+            return Vec::new();
+        };
+        let bare = bare_message_id(message_id);
+        vec![message_id_key(bare), message_id_key(&format!("<{bare}>"))]
+    }
+
+    /// Whether `hunk` is about the filtered message: by content key,
+    /// or by the `Message-ID:` of its details when its key was not
+    /// hashed from it (messages paired by size and date).
+    fn keeps_message_id(
+        &self,
+        keys: &[u64],
+        hunk: &EmailHunk,
+        details: Option<&MessageDetails>,
+    ) -> bool {
+        let Some(wanted) = &self.message_id else {
+            return true;
+        };
+        let listed = details.and_then(|details| details.message_id.as_deref());
+        keys.contains(&hunk.content_key())
+            || listed.is_some_and(|id| bare_message_id(id) == bare_message_id(wanted))
+    }
+
+    fn keeps_mailbox(&self, mailbox: &str) -> bool {
+        match &self.mailbox {
+            Some(wanted) => wanted.eq_ignore_ascii_case(mailbox),
+            None => true,
+        }
+    }
+
+    /// Narrows `record` down to its matching hunks; `None` when the
+    /// run is filtered out altogether.
+    pub fn apply(&self, mut record: HistoryRecord) -> Option<HistoryRecord> {
+        if self.since.is_some_and(|since| record.time < since) {
+            return None;
+        }
+        if !self.narrows_hunks() {
+            return Some(record);
+        }
+
+        let keys = self.message_keys();
+        let keeps_message = |hunk: &EmailHunk, details: Option<&MessageDetails>| {
+            self.keeps_mailbox(hunk.mailbox()) && self.keeps_message_id(&keys, hunk, details)
+        };
+        let report = &mut record.report;
+        report
+            .email
+            .patch
+            .retain(|entry| keeps_message(&entry.hunk, entry.details.as_ref()));
+        report
+            .rejected
+            .email
+            .retain(|hunk| keeps_message(hunk, None));
+        report
+            .blocked
+            .email
+            .retain(|b| keeps_message(&b.hunk, None));
+        if self.message_id.is_none() {
+            let keeps = |mailbox: &str| self.keeps_mailbox(mailbox);
+            report
+                .mailbox
+                .patch
+                .retain(|entry| keeps(entry.hunk.mailbox()));
+            report.rejected.mailbox.retain(|hunk| keeps(hunk.mailbox()));
+            report.blocked.mailbox.retain(|b| keeps(b.hunk.mailbox()));
+        } else {
+            report.mailbox.patch.clear();
+            report.rejected.mailbox.clear();
+            report.blocked.mailbox.clear();
+        }
+
+        let empty = report.mailbox.patch.is_empty()
+            && report.email.patch.is_empty()
+            && report.rejected.is_empty()
+            && report.blocked.is_empty();
+        (!empty).then_some(record)
+    }
+}
+
+fn bare_message_id(message_id: &str) -> &str {
+    message_id.trim_start_matches('<').trim_end_matches('>')
+}
+
+/// Output of `neverest log`: matching runs, oldest first.
+#[derive(Debug, Serialize)]
+pub struct HistoryLog {
+    pub records: Vec<HistoryRecord>,
+}
+
+impl fmt::Display for HistoryLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.records.is_empty() {
+            return write!(f, "No sync recorded");
+        }
+
+        for (i, record) in self.records.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{record}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for HistoryRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let report = &self.report;
+        let hunks = report.mailbox.patch.len() + report.email.patch.len();
+        let errors = report
+            .mailbox
+            .patch
+            .iter()
+            .filter(|e| e.error.is_some())
+            .count()
+            + report
+                .email
+                .patch
+                .iter()
+                .filter(|e| e.error.is_some())
+                .count();
+
+        write!(
+            f,
+            "{time} `{account}`: {hunks} hunks",
+            time = self.time.format("%Y-%m-%d %H:%M:%S"),
+            account = self.account,
+        )?;
+        if errors > 0 {
+            write!(f, ", {errors} errors")?;
+        }
+        if report.dry_run {
+            write!(f, " (dry run)")?;
+        }
+        writeln!(f)?;
+
+        for entry in &report.mailbox.patch {
+            write_entry(f, entry)?;
+        }
+        for entry in &report.email.patch {
+            write_entry(f, entry)?;
+            if let Some(details) = &entry.details {
+                writeln!(f, "   {details}")?;
+            }
+        }
+        for hunk in &report.rejected.mailbox {
+            writeln!(f, " - {hunk} (rejected)")?;
+        }
+        for hunk in &report.rejected.email {
+            writeln!(f, " - {hunk} (rejected)")?;
+        }
+        for blocked in &report.blocked.mailbox {
+            writeln!(f, " - {blocked}")?;
+        }
+        for blocked in &report.blocked.email {
+            writeln!(f, " - {blocked}")?;
+        }
+        Ok(())
+    }
+}
+
+fn write_entry<H: fmt::Display>(f: &mut fmt::Formatter<'_>, entry: &PatchEntry<H>) -> fmt::Result {
+    match &entry.error {
+        Some(err) => writeln!(f, " - {entry}: {err}"),
+        None => writeln!(f, " - {entry}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::{side::Side, sync::hunk::MailboxHunk};
+
+    fn record(entries: Vec<EmailHunk>) -> HistoryRecord {
+        let mut report = SyncReport {
+            account: "work".into(),
+            ..Default::default()
+        };
+        report.mailbox.patch.push(PatchEntry::new(
+            MailboxHunk::Create {
+                side: Side::Right,
+                mailbox: "Archive".into(),
+            },
+            None,
+        ));
+        for hunk in entries {
+            report.email.patch.push(PatchEntry::new(hunk, None));
+        }
+        HistoryRecord {
+            time: Local::now(),
+            account: "work".into(),
+            report,
+        }
+    }
+
+    fn delete(mailbox: &str, content_key: u64) -> EmailHunk {
+        EmailHunk::Delete {
+            side: Side::Left,
+            mailbox: mailbox.into(),
+            id: "1".into(),
+            content_key,
+        }
+    }
+
+    #[test]
+    fn message_id_filter_matches_content_keys_with_or_without_brackets() {
+        let key = message_id_key("<a@example.org>");
+        let record = record(vec![delete("INBOX", key), delete("INBOX", 7)]);
+        let filter = HistoryFilter {
+            message_id: Some("a@example.org".into()),
+            ..Default::default()
+        };
+
+        let record = filter.apply(record).unwrap();
+        assert!(record.report.mailbox.patch.is_empty());
+        assert_eq!(record.report.email.patch.len(), 1);
+        assert_eq!(record.report.email.patch[0].hunk.content_key(), key);
+    }
+
+    #[test]
+    fn message_id_filter_matches_listed_message_ids() {
+        let mut record = record(vec![delete("INBOX", 7), delete("INBOX", 8)]);
+        record.report.email.patch[0].details = Some(MessageDetails {
+            message_id: Some("<a@example.org>".into()),
+            subject: String::new(),
+            from: None,
+            date: None,
+            size: 0,
+        });
+        let filter = HistoryFilter {
+            message_id: Some("a@example.org".into()),
+            ..Default::default()
+        };
+
+        let record = filter.apply(record).unwrap();
+        assert_eq!(record.report.email.patch.len(), 1);
+        assert_eq!(record.report.email.patch[0].hunk.content_key(), 7);
+    }
+
+    #[test]
+    fn mailbox_filter_drops_runs_without_matching_hunks() {
+        let filter = HistoryFilter {
+            mailbox: Some("inbox".into()),
+            ..Default::default()
+        };
+        assert!(filter.apply(record(vec![delete("INBOX", 1)])).is_some());
+        assert!(filter.apply(record(vec![delete("Sent", 1)])).is_none());
+    }
+
+    #[test]
+    fn appended_records_are_loaded_back_across_rotations() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("history.jsonl");
+
+        let report = record(vec![EmailHunk::AddFlags {
+            side: Side::Right,
+            mailbox: "INBOX".into(),
+            id: "9".into(),
+            flags: BTreeSet::new(),
+            content_key: 9,
+        }])
+        .report;
+        append(&path, &report).unwrap();
+        rotate(&path).unwrap();
+        append(&path, &report).unwrap();
+
+        let records = load(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].report.email.patch[0].hunk.content_key(), 9);
+    }
+}
@@ -0,0 +1,397 @@
+// This file is part of Neverest, a CLI to synchronize emails.
+//
+// Copyright (C) 2024-2026  soywod <pimalaya.org@posteo.net>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Account hooks: user commands run before the sync, after it, and
+//! once for the messages copied to a side.
+//!
+//! Hooks only ever get the data of the run (the report, the envelopes
+//! of the new messages) through stdin and `NEVEREST_*` variables: the
+//! account config is never handed over, and the environment is cleared
+//! but for [`INHERITED_ENV`], so credentials exported for the backends
+//! do not leak either. Post-sync hooks run once the snapshot is
+//! persisted, so a failing hook is reported without affecting the
+//! cached state.
+
+#[cfg(unix)]
+use std::os::unix::process::CommandExt;
+use std::{
+    env,
+    ffi::OsString,
+    io::{Read, Write},
+    path::PathBuf,
+    process::{Child, Command, ExitStatus, Output, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result, bail};
+use log::{debug, warn};
+use serde::Serialize;
+
+use crate::{
+    client,
+    config::{AccountConfig, HookCommand},
+    side::Side,
+    sync::{
+        hunk::EmailHunk,
+        report::{HookFailure, MessageDetails, SyncReport},
+    },
+};
+
+/// Parent variables hooks inherit, next to the `NEVEREST_*` ones.
+const INHERITED_ENV: &[&str] = &["PATH", "HOME"];
+
+/// Delay between two checks of a running hook.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// What an `on-new-message` hook reads on stdin, one JSON line per
+/// message.
+#[derive(Debug, Serialize)]
+pub struct NewMessage<'a> {
+    pub account: &'a str,
+    pub mailbox: &'a str,
+    /// Side the message was copied to.
+    pub side: Side,
+    /// Id of the message on that side.
+    pub id: &'a str,
+    /// Message file, on m2dir sides.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub envelope: Option<&'a MessageDetails>,
+}
+
+/// Runs the `pre-sync` hook, if any.
+pub fn pre_sync(account: &str, config: &AccountConfig, dry_run: bool) -> Result<()> {
+    let hooks = &config.hooks;
+    let Some(command) = &hooks.pre_sync else {
+        return Ok(());
+    };
+    let env = base_env("pre-sync", account, dry_run);
+    run(command, &env, None, timeout(config)).context("Pre-sync hook failed, sync aborted")
+}
+
+/// Runs the `on-new-message` hook once for the messages copied during
+/// the run, then the `post-sync` hook with the report. Failures are
+/// added to [`SyncReport::hooks`].
+pub fn post_sync(config: &AccountConfig, report: &mut SyncReport) {
+    if let Some(command) = &config.hooks.on_new_message {
+        if let Err(err) = on_new_messages(command, config, report) {
+            warn!("on-new-message hook failed: {err:#}");
+            report.hooks.push(HookFailure {
+                hook: "on-new-message".into(),
+                mailbox: None,
+                id: None,
+                error: format!("{err:#}"),
+            });
+        }
+    }
+
+    let Some(command) = &config.hooks.post_sync else {
+        return;
+    };
+    let env = base_env("post-sync", &report.account, report.dry_run);
+    let result = serde_json::to_vec(&*report)
+        .context("Serialize report error")
+        .and_then(|input| run(command, &env, Some(input), timeout(config)));
+    if let Err(err) = result {
+        warn!("post-sync hook failed: {err:#}");
+        report.hooks.push(HookFailure {
+            hook: "post-sync".into(),
+            mailbox: None,
+            id: None,
+            error: format!("{err:#}"),
+        });
+    }
+}
+
+/// Feeds every message copied during the run to a single `command`;
+/// skipped when nothing was copied.
+fn on_new_messages(
+    command: &HookCommand,
+    config: &AccountConfig,
+    report: &SyncReport,
+) -> Result<()> {
+    let mut input = Vec::new();
+    let mut count = 0;
+
+    for entry in &report.email.patch {
+        let EmailHunk::Copy {
+            target_side,
+            mailbox,
+            ..
+        } = &entry.hunk
+        else {
+            continue;
+        };
+        let Some(id) = entry.target_id.as_deref() else {
+            continue;
+        };
+
+        let side_config = match target_side {
+            Side::Left => &config.left,
+            Side::Right => &config.right,
+        };
+        let path = client::message_path(side_config, mailbox, id).unwrap_or_else(|err| {
+            warn!("cannot locate message {id} in `{mailbox}`: {err:#}");
+            None
+        });
+        let message = NewMessage {
+            account: &report.account,
+            mailbox,
+            side: *target_side,
+            id,
+            path,
+            envelope: entry.details.as_ref(),
+        };
+
+        serde_json::to_writer(&mut input, &message).context("Serialize new message error")?;
+        input.push(b'\n');
+        count += 1;
+    }
+
+    if count == 0 {
+        return Ok(());
+    }
+
+    let mut env = base_env("on-new-message", &report.account, report.dry_run);
+    env.push(("NEVEREST_NEW_MESSAGES", count.to_string()));
+    run(command, &env, Some(input), timeout(config))
+}
+
+fn timeout(config: &AccountConfig) -> Duration {
+    Duration::from_secs(config.hooks.timeout_secs)
+}
+
+fn base_env(hook: &str, account: &str, dry_run: bool) -> Vec<(&'static str, String)> {
+    vec![
+        ("NEVEREST_HOOK", hook.to_owned()),
+        ("NEVEREST_ACCOUNT", account.to_owned()),
+        (
+            "NEVEREST_DRY_RUN",
+            if dry_run { "1" } else { "0" }.to_owned(),
+        ),
+    ]
+}
+
+/// Whether the parent variable `key` is passed on to hooks.
+fn inherits(key: &str) -> bool {
+    INHERITED_ENV.contains(&key) || key.starts_with("NEVEREST_")
+}
+
+fn inherited_env() -> impl Iterator<Item = (OsString, OsString)> {
+    env::vars_os().filter(|(key, _)| key.to_str().is_some_and(inherits))
+}
+
+/// Runs `command` with `env` set and `input` on stdin, and fails when
+/// it exits unsuccessfully, with its stderr as message, or outlives
+/// `timeout`, in which case it is killed along with the processes it
+/// spawned.
+fn run(
+    command: &HookCommand,
+    env: &[(&str, String)],
+    input: Option<Vec<u8>>,
+    timeout: Duration,
+) -> Result<()> {
+    let mut cmd = match command {
+        HookCommand::Shell(line) => {
+            let mut cmd = Command::new("sh");
+            cmd.arg("-c").arg(line);
+            cmd
+        }
+        HookCommand::Args(args) => {
+            let Some((program, args)) = args.split_first() else {
+                bail!("Hook command is empty");
+            };
+            let mut cmd = Command::new(program);
+            cmd.args(args);
+            cmd
+        }
+    };
+
+    cmd.env_clear()
+        .envs(inherited_env())
+        .envs(env.iter().map(|(key, val)| (key, val)))
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    // NOTE: the hook leads its own process group, so a timeout kills
+    // what it spawned too (`sh -c` children, pipelines).
+    #[cfg(unix)]
+    cmd.process_group(0);
+
+    debug!("running hook {command}");
+    let mut child = cmd.spawn().context(format!("Spawn hook {command} error"))?;
+
+    // NOTE: stdin is fed from a thread so a hook writing before it
+    // reads cannot deadlock on a full pipe.
+    let writer = match (input, child.stdin.take()) {
+        (Some(input), Some(mut stdin)) => Some(thread::spawn(move || stdin.write_all(&input))),
+        _ => None,
+    };
+
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let status = wait(&mut child, timeout).context(format!("Wait for hook {command} error"))?;
+    if status.is_none() {
+        kill(&mut child);
+    }
+
+    if let Some(writer) = writer {
+        // A hook is free to ignore its stdin: a broken pipe is fine.
+        if let Ok(Err(err)) = writer.join() {
+            debug!("hook {command} did not read its input: {err}");
+        }
+    }
+
+    // NOTE: the whole group is gone once killed, so nothing is left
+    // holding the pipes open.
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    let Some(status) = status else {
+        bail!("Hook {command} killed after {}s", timeout.as_secs());
+    };
+
+    let output = Output {
+        status,
+        stdout,
+        stderr,
+    };
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if !stdout.trim().is_empty() {
+        debug!("hook {command} output: {}", stdout.trim());
+    }
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        match stderr.trim() {
+            "" => bail!("Hook {command} exited with {}", output.status),
+            stderr => bail!("Hook {command} exited with {}: {stderr}", output.status),
+        }
+    }
+
+    Ok(())
+}
+
+/// Kills the hook and, on Unix, every process of the group it leads,
+/// so none of them keeps its pipes open.
+fn kill(child: &mut Child) {
+    #[cfg(unix)]
+    {
+        // SAFETY: a plain syscall on the group the hook leads; its id
+        // stays reserved until the hook is reaped below.
+        let _ = unsafe { libc::killpg(child.id() as libc::pid_t, libc::SIGKILL) };
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Reads `pipe` to its end from a thread, so that neither pipe of the
+/// hook fills up while the other is read.
+fn drain(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+/// Waits for `child` to exit; `None` once `timeout` elapsed.
+fn wait(child: &mut Child, timeout: Duration) -> std::io::Result<Option<ExitStatus>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            return Ok(None);
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    #[test]
+    fn run_feeds_input_and_reports_stderr_on_failure() {
+        let env = base_env("post-sync", "work", false);
+        let check = HookCommand::Shell(
+            r#"test "$NEVEREST_ACCOUNT" = work && grep -q '"dry_run":false' && echo oops >&2 && exit 3"#
+                .into(),
+        );
+
+        let err = run(
+            &check,
+            &env,
+            Some(br#"{"dry_run":false}"#.to_vec()),
+            TIMEOUT,
+        )
+        .unwrap_err();
+        let err = format!("{err:#}");
+        assert!(err.contains("oops"), "{err}");
+
+        let ok = HookCommand::Args(vec!["true".into()]);
+        run(&ok, &env, Some(b"ignored".to_vec()), TIMEOUT).unwrap();
+        assert!(run(&HookCommand::Args(Vec::new()), &env, None, TIMEOUT).is_err());
+    }
+
+    #[test]
+    fn run_kills_hooks_outliving_the_timeout() {
+        let env = base_env("post-sync", "work", false);
+        let slow = HookCommand::Args(vec!["sleep".into(), "10".into()]);
+
+        let started = Instant::now();
+        let err = run(&slow, &env, None, Duration::from_millis(100)).unwrap_err();
+        assert!(format!("{err:#}").contains("killed"), "{err:#}");
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn run_kills_what_timed_out_hooks_spawned() {
+        let env = base_env("post-sync", "work", false);
+        // The background sleep holds stdout open: unless it is killed
+        // too, draining the pipe waits for it.
+        let slow = HookCommand::Shell("sleep 10 & wait".into());
+
+        let started = Instant::now();
+        let err = run(&slow, &env, None, Duration::from_millis(100)).unwrap_err();
+        assert!(format!("{err:#}").contains("killed"), "{err:#}");
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn only_path_home_and_neverest_variables_are_inherited() {
+        assert!(inherits("PATH"));
+        assert!(inherits("HOME"));
+        assert!(inherits("NEVEREST_CONFIG"));
+        assert!(!inherits("IMAP_PASSWORD"));
+        assert!(!inherits("AWS_SECRET_ACCESS_KEY"));
+    }
+}
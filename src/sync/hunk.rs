@@ -18,17 +18,24 @@
 //! Atomic sync work units: mailbox / message / flag hunks emitted by
 //! the diff and applied by the worker pool.
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 
-use anyhow::Result;
+use anyhow::{Result, bail};
 use io_email::{client::EmailClientStd, flag::Flag};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-use crate::side::Side;
+use crate::{
+    client::{self, GuardedWrite},
+    side::Side,
+};
+
+/// Bodies above this size (bytes) are copied one at a time by the
+/// pool.
+pub const LARGE_COPY_BYTES: u64 = 8 * 1024 * 1024;
 
 /// Mailbox-level patch hunk: create or delete a mailbox on one side.
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 #[serde(tag = "kind", rename_all = "kebab-case")]
 pub enum MailboxHunk {
     Create { side: Side, mailbox: String },
@@ -48,6 +55,13 @@ impl MailboxHunk {
         }
         Ok(())
     }
+
+    /// Sides whose connection the hunk talks to.
+    pub fn sides(&self) -> &'static [Side] {
+        match self {
+            Self::Create { side, .. } | Self::Delete { side, .. } => side.as_slice(),
+        }
+    }
 }
 
 impl fmt::Display for MailboxHunk {
@@ -60,8 +74,8 @@ impl fmt::Display for MailboxHunk {
 }
 
 /// Message-level patch hunk; `content_key` is the cross-side alignment
-/// key, skipped from JSON to keep the report shape stable.
-#[derive(Clone, Debug, Serialize)]
+/// key, serialized along so saved plans can be applied back.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 #[serde(tag = "kind", rename_all = "kebab-case")]
 pub enum EmailHunk {
     /// Copy a message from `source_side` to `target_side`; `apply`
@@ -72,7 +86,8 @@ pub enum EmailHunk {
         mailbox: String,
         source_id: String,
         flags: BTreeSet<Flag>,
-        #[serde(skip)]
+        /// Source body size as listed, in bytes.
+        size: u64,
         content_key: u64,
     },
     /// Add `flags` on `side`'s copy of the message.
@@ -81,7 +96,6 @@ pub enum EmailHunk {
         mailbox: String,
         id: String,
         flags: BTreeSet<Flag>,
-        #[serde(skip)]
         content_key: u64,
     },
     /// Remove `flags` from `side`'s copy of the message.
@@ -90,7 +104,6 @@ pub enum EmailHunk {
         mailbox: String,
         id: String,
         flags: BTreeSet<Flag>,
-        #[serde(skip)]
         content_key: u64,
     },
     /// Delete `side`'s copy of the message via `delete_message`.
@@ -98,33 +111,24 @@ pub enum EmailHunk {
         side: Side,
         mailbox: String,
         id: String,
-        #[serde(skip)]
         content_key: u64,
     },
 }
 
 impl EmailHunk {
     /// Applies the hunk; returns `Some(new_id)` for a successful
-    /// `Copy` and `None` for the other variants.
+    /// `Copy` and `None` for the other variants. Flag and delete hunks
+    /// are written as is: the pool checks them against their side's
+    /// [`WriteGuard`](client::WriteGuard) first.
     pub fn apply(
         &self,
         left: &mut EmailClientStd,
         right: &mut EmailClientStd,
     ) -> Result<Option<String>> {
         match self {
-            Self::Copy {
-                source_side,
-                target_side,
-                mailbox,
-                source_id,
-                flags,
-                ..
-            } => {
-                let (source, target) = Side::pair_mut(*source_side, *target_side, left, right)?;
-                let raw = source.get_message(mailbox, source_id)?;
-                let flag_list: Vec<Flag> = flags.iter().cloned().collect();
-                let target_id = target.add_message(mailbox, &flag_list, raw)?;
-                Ok(Some(target_id))
+            Self::Copy { .. } => {
+                let raw = self.fetch(left, right)?;
+                Ok(Some(self.append(left, right, raw)?))
             }
             Self::AddFlags {
                 side,
@@ -134,8 +138,9 @@ impl EmailHunk {
                 ..
             } => {
                 let flag_list: Vec<Flag> = flags.iter().cloned().collect();
-                side.client_mut(left, right)
-                    .add_flags(mailbox, &[id.as_str()], &flag_list)?;
+                let client = side.client_mut(left, right);
+                let write = GuardedWrite::AddFlags(&flag_list);
+                client::write_unguarded(client, mailbox, &[id.as_str()], write)?;
                 Ok(None)
             }
             Self::RemoveFlags {
@@ -146,18 +151,128 @@ impl EmailHunk {
                 ..
             } => {
                 let flag_list: Vec<Flag> = flags.iter().cloned().collect();
-                side.client_mut(left, right)
-                    .delete_flags(mailbox, &[id.as_str()], &flag_list)?;
+                let client = side.client_mut(left, right);
+                let write = GuardedWrite::RemoveFlags(&flag_list);
+                client::write_unguarded(client, mailbox, &[id.as_str()], write)?;
                 Ok(None)
             }
             Self::Delete {
                 side, mailbox, id, ..
             } => {
-                side.client_mut(left, right).delete_message(mailbox, id)?;
+                let client = side.client_mut(left, right);
+                client::write_unguarded(client, mailbox, &[id.as_str()], GuardedWrite::Delete)?;
                 Ok(None)
             }
         }
     }
+
+    /// Whether the hunk is a `Copy` of a body above [`LARGE_COPY_BYTES`].
+    pub fn is_large(&self) -> bool {
+        matches!(self, Self::Copy { size, .. } if *size > LARGE_COPY_BYTES)
+    }
+
+    /// Fetches the source body of a `Copy` hunk. Read-only, hence safe
+    /// to retry.
+    pub fn fetch(&self, left: &mut EmailClientStd, right: &mut EmailClientStd) -> Result<Vec<u8>> {
+        let Self::Copy {
+            source_side,
+            mailbox,
+            source_id,
+            ..
+        } = self
+        else {
+            bail!("Only copy hunks can fetch a message");
+        };
+        let source = source_side.client_mut(left, right);
+        Ok(source.get_message(mailbox, source_id)?)
+    }
+
+    /// Appends an already fetched `raw` body of a `Copy` hunk to its
+    /// target side; returns the new id.
+    pub fn append(
+        &self,
+        left: &mut EmailClientStd,
+        right: &mut EmailClientStd,
+        raw: Vec<u8>,
+    ) -> Result<String> {
+        let Self::Copy {
+            target_side,
+            mailbox,
+            flags,
+            ..
+        } = self
+        else {
+            bail!("Only copy hunks can append a message");
+        };
+        let flag_list: Vec<Flag> = flags.iter().cloned().collect();
+        // TODO: carry the source received date (IMAP INTERNALDATE, JMAP
+        // `receivedAt`) to APPEND / `Email/import` and the m2dir mtime
+        // once `io-email` lists it and takes it on add.
+        let id = target_side
+            .client_mut(left, right)
+            .add_message(mailbox, &flag_list, raw)?;
+        Ok(id)
+    }
+
+    /// `(source, target)` sides of a `Copy` hunk, each as a slice for
+    /// the pool; both sides for the other variants.
+    pub fn copy_sides(&self) -> (&'static [Side], &'static [Side]) {
+        match self {
+            Self::Copy {
+                source_side,
+                target_side,
+                ..
+            } => (source_side.as_slice(), target_side.as_slice()),
+            _ => (self.sides(), self.sides()),
+        }
+    }
+
+    /// Message id targeted by a flag hunk.
+    pub fn flag_id(&self) -> Option<&str> {
+        match self {
+            Self::AddFlags { id, .. } | Self::RemoveFlags { id, .. } => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Content key of the message the hunk is about.
+    pub fn content_key(&self) -> u64 {
+        match self {
+            Self::Copy { content_key, .. }
+            | Self::AddFlags { content_key, .. }
+            | Self::RemoveFlags { content_key, .. }
+            | Self::Delete { content_key, .. } => *content_key,
+        }
+    }
+
+    /// Side, mailbox and id of the message a flag or delete hunk
+    /// writes, checked against that side's
+    /// [`WriteGuard`](client::WriteGuard); `None` for `Copy`.
+    pub fn guarded_write(&self) -> Option<(Side, &str, &str)> {
+        match self {
+            Self::Copy { .. } => None,
+            Self::AddFlags {
+                side, mailbox, id, ..
+            }
+            | Self::RemoveFlags {
+                side, mailbox, id, ..
+            }
+            | Self::Delete {
+                side, mailbox, id, ..
+            } => Some((*side, mailbox, id)),
+        }
+    }
+
+    /// Sides whose connection the hunk talks to: both for `Copy`, the
+    /// touched side otherwise.
+    pub fn sides(&self) -> &'static [Side] {
+        match self {
+            Self::Copy { .. } => &[Side::Left, Side::Right],
+            Self::AddFlags { side, .. }
+            | Self::RemoveFlags { side, .. }
+            | Self::Delete { side, .. } => side.as_slice(),
+        }
+    }
 }
 
 impl fmt::Display for EmailHunk {
@@ -204,9 +319,165 @@ impl fmt::Display for EmailHunk {
     }
 }
 
+/// Pool work unit: one hunk, or a batch of flag hunks applied as a
+/// single request.
+pub enum EmailWork {
+    Single(EmailHunk),
+    Flags(FlagBatch),
+}
+
+impl EmailWork {
+    /// Hunks the unit stands for, in queue order.
+    pub fn into_hunks(self) -> Vec<EmailHunk> {
+        match self {
+            Self::Single(hunk) => vec![hunk],
+            Self::Flags(batch) => batch.hunks,
+        }
+    }
+}
+
+impl fmt::Display for EmailWork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Single(hunk) => write!(f, "{hunk}"),
+            Self::Flags(batch) => write!(f, "{batch}"),
+        }
+    }
+}
+
+/// `AddFlags` / `RemoveFlags` hunks sharing mailbox, side, direction
+/// and flag set; sent as one `add_flags` / `delete_flags` call over
+/// every id (one UID STORE, one `Email/set`).
+pub struct FlagBatch {
+    pub side: Side,
+    pub mailbox: String,
+    /// `true` for `RemoveFlags` hunks.
+    pub remove: bool,
+    pub flags: BTreeSet<Flag>,
+    pub hunks: Vec<EmailHunk>,
+}
+
+impl FlagBatch {
+    /// Sends one flag request covering every hunk's id but the
+    /// `modified` ones, refused by the
+    /// [`WriteGuard`](client::WriteGuard) check.
+    pub fn apply(
+        &self,
+        left: &mut EmailClientStd,
+        right: &mut EmailClientStd,
+        modified: &[String],
+    ) -> Result<()> {
+        let ids: Vec<&str> = self
+            .hunks
+            .iter()
+            .filter_map(EmailHunk::flag_id)
+            .filter(|id| !modified.iter().any(|m| m == id))
+            .collect();
+        let flag_list: Vec<Flag> = self.flags.iter().cloned().collect();
+        let client = self.side.client_mut(left, right);
+        let write = if self.remove {
+            GuardedWrite::RemoveFlags(&flag_list)
+        } else {
+            GuardedWrite::AddFlags(&flag_list)
+        };
+        client::write_unguarded(client, &self.mailbox, &ids, write)
+    }
+}
+
+/// Flag or delete hunk refused by the
+/// [`WriteGuard`](client::WriteGuard) check because the message
+/// changed after it was listed; re-diffed on the next run.
+#[derive(Debug)]
+pub struct ModifiedConcurrently;
+
+impl fmt::Display for ModifiedConcurrently {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "skipped: modified concurrently")
+    }
+}
+
+impl std::error::Error for ModifiedConcurrently {}
+
+impl fmt::Display for FlagBatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            side,
+            mailbox,
+            remove,
+            flags,
+            hunks,
+        } = self;
+        let flags = format_flag_list(flags);
+        let n = hunks.len();
+        if *remove {
+            write!(
+                f,
+                "remove {flags} from {n} messages in `{mailbox}` on {side}"
+            )
+        } else {
+            write!(f, "add {flags} to {n} messages in `{mailbox}` on {side}")
+        }
+    }
+}
+
+/// Groups flag hunks sharing mailbox, side, direction and flag set
+/// into [`FlagBatch`]es of at most `chunk_size(side)` ids; other hunks
+/// and lone flag hunks stay [`EmailWork::Single`].
+pub fn batch_email_hunks(
+    hunks: Vec<EmailHunk>,
+    chunk_size: impl Fn(Side) -> usize,
+) -> Vec<EmailWork> {
+    let mut work = Vec::new();
+    let mut groups: BTreeMap<(Side, String, bool, BTreeSet<Flag>), Vec<EmailHunk>> =
+        BTreeMap::new();
+
+    for hunk in hunks {
+        let key = match &hunk {
+            EmailHunk::AddFlags {
+                side,
+                mailbox,
+                flags,
+                ..
+            } => (*side, mailbox.clone(), false, flags.clone()),
+            EmailHunk::RemoveFlags {
+                side,
+                mailbox,
+                flags,
+                ..
+            } => (*side, mailbox.clone(), true, flags.clone()),
+            _ => {
+                work.push(EmailWork::Single(hunk));
+                continue;
+            }
+        };
+        groups.entry(key).or_default().push(hunk);
+    }
+
+    for ((side, mailbox, remove, flags), mut group) in groups {
+        let size = chunk_size(side).max(1);
+        while !group.is_empty() {
+            let rest = group.split_off(size.min(group.len()));
+            let chunk = std::mem::replace(&mut group, rest);
+            if chunk.len() == 1 {
+                work.extend(chunk.into_iter().map(EmailWork::Single));
+            } else {
+                work.push(EmailWork::Flags(FlagBatch {
+                    side,
+                    mailbox: mailbox.clone(),
+                    remove,
+                    flags: flags.clone(),
+                    hunks: chunk,
+                }));
+            }
+        }
+    }
+
+    work
+}
+
 /// Lowercase comma-joined flag list wrapped in brackets, e.g.
 /// `[\seen, \flagged]`.
-fn format_flag_list(flags: &BTreeSet<Flag>) -> String {
+pub fn format_flag_list(flags: &BTreeSet<Flag>) -> String {
     let mut out = String::from("[");
     let mut first = true;
     for flag in flags {
@@ -223,3 +494,95 @@ fn format_flag_list(flags: &BTreeSet<Flag>) -> String {
     out.push(']');
     out
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_seen(side: Side, id: &str) -> EmailHunk {
+        EmailHunk::AddFlags {
+            side,
+            mailbox: "INBOX".into(),
+            id: id.into(),
+            flags: BTreeSet::from([Flag::from_raw("\\Seen")]),
+            content_key: 0,
+        }
+    }
+
+    fn copy(source_side: Side, id: &str) -> EmailHunk {
+        copy_sized(source_side, id, 1024)
+    }
+
+    fn copy_sized(source_side: Side, id: &str, size: u64) -> EmailHunk {
+        EmailHunk::Copy {
+            source_side,
+            target_side: match source_side {
+                Side::Left => Side::Right,
+                Side::Right => Side::Left,
+            },
+            mailbox: "INBOX".into(),
+            source_id: id.into(),
+            flags: BTreeSet::new(),
+            size,
+            content_key: 0,
+        }
+    }
+
+    fn delete(side: Side, id: &str) -> EmailHunk {
+        EmailHunk::Delete {
+            side,
+            mailbox: "INBOX".into(),
+            id: id.into(),
+            content_key: 0,
+        }
+    }
+
+    #[test]
+    fn flag_hunks_sharing_side_and_flags_are_batched() {
+        let hunks = vec![
+            add_seen(Side::Left, "1"),
+            delete(Side::Left, "9"),
+            add_seen(Side::Left, "2"),
+            add_seen(Side::Right, "3"),
+            add_seen(Side::Left, "4"),
+        ];
+
+        let work = batch_email_hunks(hunks, |_| 100);
+        assert_eq!(work.len(), 3);
+
+        let batch = work
+            .iter()
+            .find_map(|w| match w {
+                EmailWork::Flags(batch) => Some(batch),
+                EmailWork::Single(_) => None,
+            })
+            .unwrap();
+        assert_eq!(batch.side, Side::Left);
+        let ids: Vec<_> = batch.hunks.iter().filter_map(EmailHunk::flag_id).collect();
+        assert_eq!(ids, ["1", "2", "4"]);
+    }
+
+    #[test]
+    fn batches_are_chunked_per_side() {
+        let hunks = (0..5)
+            .map(|i| add_seen(Side::Right, &i.to_string()))
+            .collect();
+        let work = batch_email_hunks(hunks, |_| 2);
+
+        let sizes: Vec<_> = work.into_iter().map(|w| w.into_hunks().len()).collect();
+        assert_eq!(sizes, [2, 2, 1]);
+    }
+
+    #[test]
+    fn copies_stay_single() {
+        let hunks = vec![
+            copy(Side::Left, "1"),
+            copy(Side::Right, "2"),
+            copy_sized(Side::Left, "huge", LARGE_COPY_BYTES + 1),
+        ];
+        let work = batch_email_hunks(hunks, |_| 100);
+
+        assert_eq!(work.len(), 3);
+        assert!(work.iter().all(|w| matches!(w, EmailWork::Single(_))));
+    }
+}
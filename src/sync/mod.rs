@@ -22,8 +22,17 @@ mod sync;
 
 pub mod cache;
 pub mod diff;
+pub mod history;
+pub mod hook;
 pub mod hunk;
+pub mod observer;
+pub mod plan;
 pub mod pool;
+pub mod progress;
 pub mod report;
+pub mod retry;
+pub mod review;
+pub mod stats;
+pub mod throttle;
 
 pub use sync::*;
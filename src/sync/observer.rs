@@ -0,0 +1,150 @@
+// This file is part of Neverest, a CLI to synchronize emails.
+//
+// Copyright (C) 2024-2026  soywod <pimalaya.org@posteo.net>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Front-end hook of [`run`](crate::sync::run): stages, mailboxes and
+//! hunk outcomes as they happen, plus the confirmation asked by
+//! [`SyncMode::Review`](crate::sync::SyncMode::Review).
+//!
+//! Every method is called from the thread running the sync, so an
+//! observer needs neither locking nor `Send`.
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::sync::review::ReviewItem;
+
+/// Step of [`run`](crate::sync::run) an event is about.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Stage {
+    /// Listing the mailboxes of both sides.
+    Probe,
+    /// Creating and deleting mailboxes.
+    MailboxPatch,
+    /// Listing, diffing and applying every mailbox.
+    Sync,
+    /// Applying reviewed or planned hunks.
+    Apply,
+    /// Saving the cache snapshot.
+    Persist,
+}
+
+/// Outcome of an applied hunk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HunkStatus {
+    Applied,
+    Failed,
+    /// Left for the next run, e.g. modified concurrently.
+    Skipped,
+}
+
+/// A hunk of `mailbox` came back from the pool, `applied` out of the
+/// `total` hunks of the mailbox.
+#[derive(Clone, Debug, Serialize)]
+pub struct HunkProgress {
+    pub mailbox: String,
+    pub hunk: String,
+    pub status: HunkStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub applied: usize,
+    pub total: usize,
+}
+
+impl HunkProgress {
+    pub fn percent(&self) -> usize {
+        self.applied * 100 / self.total.max(1)
+    }
+}
+
+/// Receives what a sync is doing. Every method defaults to doing
+/// nothing, and [`review`](SyncObserver::review) to approving every
+/// hunk.
+pub trait SyncObserver {
+    /// `stage` starts; `message` describes it for humans.
+    fn stage_started(&mut self, stage: Stage, message: &str) {
+        let _ = (stage, message);
+    }
+
+    /// Human-readable progress within `stage`.
+    fn stage_progress(&mut self, stage: Stage, message: &str) {
+        let _ = (stage, message);
+    }
+
+    /// `stage` ended after `elapsed_ms`; `message` sums it up.
+    fn stage_finished(&mut self, stage: Stage, message: &str, elapsed_ms: u64) {
+        let _ = (stage, message, elapsed_ms);
+    }
+
+    /// `mailbox` went through `stage`, `done` out of `total`.
+    fn mailbox_finished(&mut self, stage: Stage, mailbox: &str, done: usize, total: usize) {
+        let _ = (stage, mailbox, done, total);
+    }
+
+    fn hunk_finished(&mut self, hunk: &HunkProgress) {
+        let _ = hunk;
+    }
+
+    /// Decides which hunks get applied in
+    /// [`SyncMode::Review`](crate::sync::SyncMode::Review): returns
+    /// one approval per item, in order; an error aborts the sync
+    /// before anything else is applied.
+    fn review(&mut self, title: &str, items: &[ReviewItem]) -> Result<Vec<bool>> {
+        let _ = title;
+        Ok(vec![true; items.len()])
+    }
+}
+
+/// Observer ignoring everything and approving every hunk.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct QuietObserver;
+
+impl SyncObserver for QuietObserver {}
+
+/// Forwards every event to both observers; the review is left to the
+/// first one.
+impl<A: SyncObserver, B: SyncObserver> SyncObserver for (A, B) {
+    fn stage_started(&mut self, stage: Stage, message: &str) {
+        self.0.stage_started(stage, message);
+        self.1.stage_started(stage, message);
+    }
+
+    fn stage_progress(&mut self, stage: Stage, message: &str) {
+        self.0.stage_progress(stage, message);
+        self.1.stage_progress(stage, message);
+    }
+
+    fn stage_finished(&mut self, stage: Stage, message: &str, elapsed_ms: u64) {
+        self.0.stage_finished(stage, message, elapsed_ms);
+        self.1.stage_finished(stage, message, elapsed_ms);
+    }
+
+    fn mailbox_finished(&mut self, stage: Stage, mailbox: &str, done: usize, total: usize) {
+        self.0.mailbox_finished(stage, mailbox, done, total);
+        self.1.mailbox_finished(stage, mailbox, done, total);
+    }
+
+    fn hunk_finished(&mut self, hunk: &HunkProgress) {
+        self.0.hunk_finished(hunk);
+        self.1.hunk_finished(hunk);
+    }
+
+    fn review(&mut self, title: &str, items: &[ReviewItem]) -> Result<Vec<bool>> {
+        self.0.review(title, items)
+    }
+}
@@ -0,0 +1,299 @@
+// This file is part of Neverest, a CLI to synchronize emails.
+//
+// Copyright (C) 2024-2026  soywod <pimalaya.org@posteo.net>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Saved patch of `sync --plan-out`, applied later by `neverest apply`.
+//!
+//! Next to the hunks, every mailbox records a [`Checkpoint`] per side:
+//! digests of the listing and of the cached baseline the hunks were
+//! diffed from. Applying refuses the plan once a checkpoint moved,
+//! then re-runs the diff and only applies planned hunks the fresh diff
+//! still yields; the others are reported stale.
+
+use std::{
+    collections::{BTreeSet, HashMap, hash_map::DefaultHasher},
+    fs,
+    hash::{Hash, Hasher},
+    path::Path,
+};
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::MailboxFilter,
+    side::Side,
+    sync::{
+        cache::MessageSnapshots,
+        diff::EnvelopePairs,
+        hunk::{EmailHunk, MailboxHunk},
+        review::Reviewable,
+    },
+};
+
+/// Plan file format version, bumped on incompatible changes.
+const PLAN_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Plan {
+    pub version: u32,
+    pub account: String,
+    pub created_at: DateTime<Local>,
+    /// Mailbox patch.
+    pub mailbox: Vec<MailboxHunk>,
+    /// Message patch of every mailbox synced, including those without
+    /// hunks, sorted by mailbox.
+    pub mailboxes: Vec<MailboxPlan>,
+}
+
+impl Plan {
+    pub fn new(account: impl Into<String>) -> Self {
+        Self {
+            version: PLAN_VERSION,
+            account: account.into(),
+            created_at: Local::now(),
+            mailbox: Vec::new(),
+            mailboxes: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let bytes = fs::read(path).context(format!("Read plan `{}` error", path.display()))?;
+        let plan: Self = serde_json::from_slice(&bytes)
+            .context(format!("Parse plan `{}` error", path.display()))?;
+        if plan.version != PLAN_VERSION {
+            bail!(
+                "Plan `{}` has version {}, expected {PLAN_VERSION}",
+                path.display(),
+                plan.version
+            );
+        }
+        Ok(plan)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let bytes = serde_json::to_vec_pretty(self).context("Serialize plan error")?;
+        fs::write(path, bytes).context(format!("Write plan `{}` error", path.display()))?;
+        Ok(())
+    }
+
+    /// Restricts the sync to the mailboxes the plan is about, so the
+    /// fresh diff covers the same ground.
+    pub fn filter(&self) -> MailboxFilter {
+        let names: BTreeSet<&str> = self
+            .mailbox
+            .iter()
+            .map(Reviewable::mailbox)
+            .chain(self.mailboxes.iter().map(|plan| plan.mailbox.as_str()))
+            .collect();
+        MailboxFilter::Include(names.into_iter().map(str::to_owned).collect())
+    }
+
+    pub fn mailbox_plan(&self, mailbox: &str) -> Option<&MailboxPlan> {
+        self.mailboxes.iter().find(|plan| plan.mailbox == mailbox)
+    }
+
+    pub fn hunk_count(&self) -> usize {
+        let hunks: usize = self.mailboxes.iter().map(|plan| plan.hunks.len()).sum();
+        self.mailbox.len() + hunks
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MailboxPlan {
+    pub mailbox: String,
+    pub left: Checkpoint,
+    pub right: Checkpoint,
+    pub hunks: Vec<EmailHunk>,
+}
+
+impl MailboxPlan {
+    pub fn checkpoint(&self, side: Side) -> &Checkpoint {
+        match side {
+            Side::Left => &self.left,
+            Side::Right => &self.right,
+        }
+    }
+}
+
+/// State of one side of a mailbox when its hunks were computed.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Checkpoint {
+    /// Digest of the listed messages and their flags.
+    pub listing: String,
+    /// Digest of the cached baseline the listing was diffed against.
+    pub baseline: String,
+}
+
+impl Checkpoint {
+    pub fn new(pairs: &EnvelopePairs, baseline: Option<&MessageSnapshots>) -> Self {
+        let listing = pairs
+            .iter()
+            .map(|(key, envelope)| (key.to_string(), envelope.id.as_str(), &envelope.flags));
+        let baseline = baseline
+            .into_iter()
+            .flatten()
+            .map(|(key, entry)| (key.clone(), entry.id.as_str(), &entry.flags));
+        Self {
+            listing: digest(listing),
+            baseline: digest(baseline),
+        }
+    }
+}
+
+/// Order-independent digest of `(content key, id, flags)` entries.
+fn digest<'a, F: Hash + Ord + 'a>(entries: impl Iterator<Item = (String, &'a str, F)>) -> String {
+    let entries: BTreeSet<(String, &str, F)> = entries.collect();
+    let mut hasher = DefaultHasher::new();
+    entries.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Skip reason of a planned hunk the fresh diff no longer yields.
+pub const STALE: &str = "stale: changed since the plan";
+
+/// Hunk a plan can be revalidated against: hunks sharing a key are
+/// compared in full, others never are.
+pub trait Planned: PartialEq + Clone {
+    type Key: Hash + Eq;
+
+    fn plan_key(&self) -> Self::Key;
+}
+
+impl Planned for MailboxHunk {
+    type Key = (Side, String);
+
+    fn plan_key(&self) -> Self::Key {
+        match self {
+            Self::Create { side, mailbox } | Self::Delete { side, mailbox } => {
+                (*side, mailbox.clone())
+            }
+        }
+    }
+}
+
+impl Planned for EmailHunk {
+    /// `(mailbox, content key, kind)`.
+    type Key = (String, u64, &'static str);
+
+    fn plan_key(&self) -> Self::Key {
+        let (mailbox, kind) = match self {
+            Self::Copy { mailbox, .. } => (mailbox, "copy"),
+            Self::AddFlags { mailbox, .. } => (mailbox, "add-flags"),
+            Self::RemoveFlags { mailbox, .. } => (mailbox, "remove-flags"),
+            Self::Delete { mailbox, .. } => (mailbox, "delete"),
+        };
+        (mailbox.clone(), self.content_key(), kind)
+    }
+}
+
+/// Splits freshly computed `hunks` against the `planned` ones into
+/// `(planned and still computed, computed but not planned, planned
+/// but no longer computed)`.
+pub fn revalidate<H: Planned>(planned: &[H], hunks: Vec<H>) -> (Vec<H>, Vec<H>, Vec<H>) {
+    let mut pending: HashMap<H::Key, Vec<(usize, &H)>> = HashMap::new();
+    for (i, hunk) in planned.iter().enumerate() {
+        pending.entry(hunk.plan_key()).or_default().push((i, hunk));
+    }
+
+    let mut matched = vec![false; planned.len()];
+    let (valid, unplanned): (Vec<H>, Vec<H>) = hunks.into_iter().partition(|hunk| {
+        let Some(candidates) = pending.get_mut(&hunk.plan_key()) else {
+            return false;
+        };
+        let Some(pos) = candidates.iter().position(|(_, planned)| *planned == hunk) else {
+            return false;
+        };
+        let (i, _) = candidates.swap_remove(pos);
+        matched[i] = true;
+        true
+    });
+
+    let stale = planned
+        .iter()
+        .zip(matched)
+        .filter(|(_, matched)| !matched)
+        .map(|(hunk, _)| hunk.clone())
+        .collect();
+    (valid, unplanned, stale)
+}
+
+#[cfg(test)]
+mod tests {
+    use io_email::flag::Flag;
+
+    use super::*;
+
+    fn create(mailbox: &str) -> MailboxHunk {
+        MailboxHunk::Create {
+            side: Side::Right,
+            mailbox: mailbox.into(),
+        }
+    }
+
+    #[test]
+    fn revalidation_splits_valid_unplanned_and_stale() {
+        let planned = [create("A"), create("B")];
+        let fresh = vec![create("B"), create("C")];
+
+        let (valid, unplanned, stale) = revalidate(&planned, fresh);
+        assert_eq!(valid, vec![create("B")]);
+        assert_eq!(unplanned, vec![create("C")]);
+        assert_eq!(stale, vec![create("A")]);
+    }
+
+    #[test]
+    fn revalidation_tells_apart_hunks_sharing_a_key() {
+        let add = |flag: &str| EmailHunk::AddFlags {
+            side: Side::Left,
+            mailbox: "INBOX".into(),
+            id: "1".into(),
+            flags: BTreeSet::from([Flag::from_raw(flag)]),
+            content_key: 7,
+        };
+        let planned = [add("a"), add("b")];
+        let fresh = vec![add("b"), add("c")];
+
+        let (valid, unplanned, stale) = revalidate(&planned, fresh);
+        assert_eq!(valid, vec![add("b")]);
+        assert_eq!(unplanned, vec![add("c")]);
+        assert_eq!(stale, vec![add("a")]);
+    }
+
+    #[test]
+    fn plan_round_trips_through_json() {
+        let mut plan = Plan::new("work");
+        plan.mailbox.push(create("Archive"));
+        plan.mailboxes.push(MailboxPlan {
+            mailbox: "INBOX".into(),
+            left: Checkpoint::new(&Vec::new(), None),
+            right: Checkpoint::new(&Vec::new(), None),
+            hunks: vec![EmailHunk::Delete {
+                side: Side::Left,
+                mailbox: "INBOX".into(),
+                id: "42".into(),
+                content_key: u64::MAX,
+            }],
+        });
+
+        let json = serde_json::to_vec(&plan).unwrap();
+        let parsed: Plan = serde_json::from_slice(&json).unwrap();
+        assert_eq!(parsed.mailbox, plan.mailbox);
+        assert_eq!(parsed.mailboxes[0].hunks, plan.mailboxes[0].hunks);
+        assert_eq!(parsed.mailboxes[0].left, plan.mailboxes[0].left);
+    }
+}
@@ -19,40 +19,94 @@
 //! mailbox / message hunks out across paired `(left, right)` workers.
 
 use std::{
-    sync::{Arc, mpsc},
+    sync::{Arc, Mutex, PoisonError, mpsc},
     thread,
 };
 
-use anyhow::{Context, Result, bail};
+use anyhow::{Context, Error, Result, anyhow, bail};
 use crossbeam_queue::SegQueue;
 use io_email::client::EmailClientStd;
-use log::{trace, warn};
+use log::{debug, trace, warn};
 
 use crate::{
-    client,
-    config::SideConfig,
-    sync::hunk::{EmailHunk, MailboxHunk},
+    client::{self, WriteGuard},
+    config::{PoolSize, RetryConfig, SideConfig},
+    side::Side,
+    sync::{
+        hunk::{EmailHunk, EmailWork, MailboxHunk, ModifiedConcurrently, batch_email_hunks},
+        retry::{self, NotSent, is_refused, is_transient},
+        stats::RequestCounter,
+        throttle::Throttles,
+    },
 };
 
-// TODO: replace with the server-advertised IMAP LIMIT once `io-imap`
-// exposes the capability getter.
+/// Explicit IMAP pool sizes above this are warned about.
 const IMAP_SOFT_LIMIT: usize = 10;
 
+/// Ids per batched flag request: keeps IMAP UID STORE command lines
+/// reasonable and stays within the 500 objects RFC 8620 asks JMAP
+/// servers to accept in one `Email/set`.
+const FLAG_BATCH_SIZE: usize = 500;
+
+/// Per-side [`WriteGuard`]s of the mailbox being applied, shared by
+/// the workers: each guarded write moves the state forward for all.
+#[derive(Clone, Debug, Default)]
+pub struct WriteGuards {
+    pub left: Option<Arc<Mutex<WriteGuard>>>,
+    pub right: Option<Arc<Mutex<WriteGuard>>>,
+}
+
+impl WriteGuards {
+    fn side(&self, side: Side) -> Option<Arc<Mutex<WriteGuard>>> {
+        match side {
+            Side::Left => self.left.clone(),
+            Side::Right => self.right.clone(),
+        }
+    }
+}
+
 /// Both sides' worker pools paired so `Copy` hunks always have read +
 /// write ends in hand.
 pub struct Pool {
     pub left: Vec<EmailClientStd>,
     pub right: Vec<EmailClientStd>,
+    /// Side configs kept around to reopen dropped connections.
+    pub left_config: SideConfig,
+    pub right_config: SideConfig,
+    /// Backoff policy wrapped around every hunk apply; [`run`] sets
+    /// the account one.
+    ///
+    /// [`run`]: crate::sync::run
+    pub retry: RetryConfig,
+    /// Adaptive concurrency gates, one per side, kept for the whole
+    /// run (and shared by every lane) so pressure met in one mailbox
+    /// keeps slowing the next ones.
+    pub throttles: Arc<Throttles>,
+    /// Backend calls per side, shared by every lane.
+    pub requests: Arc<RequestCounter>,
+    /// Serializes large copies, so at most one large body sits in
+    /// memory at a time. Bodies are still held whole, not streamed.
+    pub large_copies: Arc<Mutex<()>>,
 }
 
 impl Pool {
-    /// Opens both sides' clients in parallel (each side parallel
-    /// internally); the first error propagates and drops everything
-    /// already opened.
+    /// Opens both sides' clients (each side parallel internally),
+    /// sized from the config; the first error propagates and drops
+    /// everything already opened.
     pub fn open(left: SideConfig, right: SideConfig) -> Result<Pool> {
+        let left_clients = open_side(&left).context("Open left pool")?;
+        let right_clients = open_side(&right).context("Open right pool")?;
+        let throttles = Arc::new(Throttles::new(left_clients.len(), right_clients.len()));
+
         Ok(Pool {
-            left: open_side(left)?,
-            right: open_side(right)?,
+            left: left_clients,
+            right: right_clients,
+            left_config: left,
+            right_config: right,
+            retry: RetryConfig::default(),
+            throttles,
+            requests: Arc::default(),
+            large_copies: Arc::new(Mutex::new(())),
         })
     }
 
@@ -61,72 +115,161 @@ impl Pool {
         self.left.len().min(self.right.len()).max(1)
     }
 
+    /// Borrows the first `(left, right)` pair, reopening one when
+    /// dropped connections shrank a side down to nothing.
+    pub fn first_mut(&mut self) -> Result<(&mut EmailClientStd, &mut EmailClientStd)> {
+        if self.left.is_empty() {
+            warn!("left pool is empty, reopening one connection");
+            let client = client::open(self.left_config.clone()).context("Reopen left side")?;
+            self.left.push(client);
+        }
+        if self.right.is_empty() {
+            warn!("right pool is empty, reopening one connection");
+            let client = client::open(self.right_config.clone()).context("Reopen right side")?;
+            self.right.push(client);
+        }
+        Ok((&mut self.left[0], &mut self.right[0]))
+    }
+
+    /// Splits the pool into at most `count` lanes, each owning a share
+    /// of the `(left, right)` pairs and sharing the run-wide policies,
+    /// so several mailboxes can be synced at once without two threads
+    /// ever driving (or re-SELECTing) the same connection. Hand the
+    /// lanes back with [`Pool::merge_lanes`].
+    pub fn split_lanes(&mut self, count: usize) -> Result<Vec<Pool>> {
+        self.first_mut()?;
+        let pairs = self.take_workers(self.worker_count());
+        let count = count.clamp(1, pairs.len());
+
+        let mut lanes: Vec<Pool> = (0..count)
+            .map(|_| Pool {
+                left: Vec::new(),
+                right: Vec::new(),
+                left_config: self.left_config.clone(),
+                right_config: self.right_config.clone(),
+                retry: self.retry.clone(),
+                throttles: self.throttles.clone(),
+                requests: self.requests.clone(),
+                large_copies: self.large_copies.clone(),
+            })
+            .collect();
+
+        for (index, (left, right)) in pairs.into_iter().enumerate() {
+            let lane = &mut lanes[index % count];
+            lane.left.push(left);
+            lane.right.push(right);
+        }
+
+        Ok(lanes)
+    }
+
+    /// Takes back the connections of lanes built by
+    /// [`Pool::split_lanes`].
+    pub fn merge_lanes(&mut self, lanes: Vec<Pool>) {
+        for lane in lanes {
+            self.left.extend(lane.left);
+            self.right.extend(lane.right);
+        }
+    }
+
     /// Fans email `hunks` out across worker threads, each owning one
     /// `(left, right)` pair for the mailbox duration; per-hunk failures
     /// are collected without stopping other workers.
+    ///
+    /// Flag hunks sharing side and flag set travel as batched
+    /// requests (see [`batch_email_hunks`]); their outcomes are still
+    /// reported per hunk. Copies stay one work unit each, so they
+    /// spread across every worker.
+    ///
+    /// Dropped connections are reopened (and re-SELECTed on
+    /// `mailbox`) by the worker; a worker that cannot reconnect
+    /// retires and its pair leaves the pool.
+    ///
+    /// `on_progress` gets `(applied, total)` once up front, then again
+    /// with every outcome as it comes back.
     pub fn apply_in_mailbox<F>(
         &mut self,
         mailbox: &str,
         hunks: Vec<EmailHunk>,
+        guards: &WriteGuards,
         mut on_progress: F,
     ) -> Result<Vec<HunkOutcome>>
     where
-        F: FnMut(usize, usize),
+        F: FnMut(usize, usize, Option<&HunkOutcome>),
     {
         let total = hunks.len();
+        self.first_mut()?;
         let worker_count = self.worker_count();
-        on_progress(0, total);
+        on_progress(0, total, None);
 
-        let queue: Arc<SegQueue<EmailHunk>> = Arc::new(SegQueue::new());
-        for hunk in hunks {
-            queue.push(hunk);
+        let queue: Arc<SegQueue<EmailWork>> = Arc::new(SegQueue::new());
+        for work in batch_email_hunks(hunks, |_| FLAG_BATCH_SIZE) {
+            queue.push(work);
         }
         let (done_tx, done_rx) = mpsc::channel::<HunkOutcome>();
 
-        let mut workers: Vec<(EmailClientStd, EmailClientStd)> = Vec::with_capacity(worker_count);
-        while workers.len() < worker_count {
-            match (self.left.pop(), self.right.pop()) {
-                (Some(l), Some(r)) => workers.push((l, r)),
-                _ => break,
-            }
-        }
+        let workers = self.take_workers(worker_count);
 
         let mut outcomes: Vec<HunkOutcome> = Vec::with_capacity(total);
+        let mut applied = 0;
 
         thread::scope(|scope| -> Result<()> {
             let mut handles = Vec::with_capacity(workers.len());
-            for (left, right) in workers.drain(..) {
+            for (left, right) in workers {
                 let q = queue.clone();
                 let tx = done_tx.clone();
-                let mailbox = mailbox.to_string();
-
-                handles.push(scope.spawn(move || email_worker(left, right, mailbox, q, tx)));
+                let worker = Worker {
+                    left,
+                    right,
+                    healer: Healer::new(&self.left_config, &self.right_config, Some(mailbox)),
+                    retry: &self.retry,
+                    throttles: &self.throttles,
+                    requests: &self.requests,
+                    large_copies: &self.large_copies,
+                    guards: guards.clone(),
+                };
+                handles.push(scope.spawn(move || email_worker(worker, q, tx)));
             }
             drop(done_tx);
 
-            let mut applied = 0;
             while let Ok(outcome) = done_rx.recv() {
                 applied += 1;
                 match &outcome.result {
                     Err(e) => trace!("{mailbox} [{applied}/{total}] {}: {e:#}", outcome.hunk),
                     Ok(_) => trace!("{mailbox} [{applied}/{total}] {}", outcome.hunk),
                 }
+                on_progress(applied, total, Some(&outcome));
                 outcomes.push(outcome);
-                on_progress(applied, total);
             }
 
             for handle in handles {
                 match handle.join() {
-                    Ok((left, right)) => {
+                    Ok(Some((left, right))) => {
                         self.left.push(left);
                         self.right.push(right);
                     }
+                    Ok(None) => {}
                     Err(_) => bail!("Email worker thread panicked"),
                 }
             }
             Ok(())
         })?;
 
+        // NOTE: every worker retired on failed reconnects; surface the
+        // hunks nobody got to as failures instead of dropping them.
+        while let Some(work) = queue.pop() {
+            for hunk in work.into_hunks() {
+                applied += 1;
+                let outcome = HunkOutcome {
+                    hunk,
+                    result: Err(no_worker_left()),
+                    retries: 0,
+                };
+                on_progress(applied, total, Some(&outcome));
+                outcomes.push(outcome);
+            }
+        }
+
         Ok(outcomes)
     }
 
@@ -137,11 +280,12 @@ impl Pool {
         mut on_progress: F,
     ) -> Result<Vec<MailboxHunkOutcome>>
     where
-        F: FnMut(usize, usize),
+        F: FnMut(usize, usize, Option<&MailboxHunkOutcome>),
     {
         let total = hunks.len();
+        self.first_mut()?;
         let worker_count = self.worker_count();
-        on_progress(0, total);
+        on_progress(0, total, None);
 
         let queue: Arc<SegQueue<MailboxHunk>> = Arc::new(SegQueue::new());
         for hunk in hunks {
@@ -149,50 +293,82 @@ impl Pool {
         }
         let (done_tx, done_rx) = mpsc::channel::<MailboxHunkOutcome>();
 
-        let mut workers: Vec<(EmailClientStd, EmailClientStd)> = Vec::with_capacity(worker_count);
-        while workers.len() < worker_count {
-            match (self.left.pop(), self.right.pop()) {
-                (Some(l), Some(r)) => workers.push((l, r)),
-                _ => break,
-            }
-        }
+        let workers = self.take_workers(worker_count);
 
         let mut outcomes: Vec<MailboxHunkOutcome> = Vec::with_capacity(total);
+        let mut applied = 0;
 
         thread::scope(|scope| -> Result<()> {
             let mut handles = Vec::with_capacity(workers.len());
-            for (left, right) in workers.drain(..) {
+            for (left, right) in workers {
                 let q = queue.clone();
                 let tx = done_tx.clone();
-                handles.push(scope.spawn(move || mailbox_worker(left, right, q, tx)));
+                let worker = Worker {
+                    left,
+                    right,
+                    healer: Healer::new(&self.left_config, &self.right_config, None),
+                    retry: &self.retry,
+                    throttles: &self.throttles,
+                    requests: &self.requests,
+                    large_copies: &self.large_copies,
+                    guards: WriteGuards::default(),
+                };
+                handles.push(scope.spawn(move || mailbox_worker(worker, q, tx)));
             }
             drop(done_tx);
 
-            let mut applied = 0;
             while let Ok(outcome) = done_rx.recv() {
                 applied += 1;
                 match &outcome.result {
                     Err(e) => trace!("mailbox [{applied}/{total}] {}: {e:#}", outcome.hunk),
                     Ok(_) => trace!("mailbox [{applied}/{total}] {}", outcome.hunk),
                 }
+                on_progress(applied, total, Some(&outcome));
                 outcomes.push(outcome);
-                on_progress(applied, total);
             }
 
             for handle in handles {
                 match handle.join() {
-                    Ok((left, right)) => {
+                    Ok(Some((left, right))) => {
                         self.left.push(left);
                         self.right.push(right);
                     }
+                    Ok(None) => {}
                     Err(_) => bail!("Mailbox worker thread panicked"),
                 }
             }
             Ok(())
         })?;
 
+        while let Some(hunk) = queue.pop() {
+            applied += 1;
+            let outcome = MailboxHunkOutcome {
+                hunk,
+                result: Err(no_worker_left()),
+                retries: 0,
+            };
+            on_progress(applied, total, Some(&outcome));
+            outcomes.push(outcome);
+        }
+
         Ok(outcomes)
     }
+
+    /// Pops up to `count` `(left, right)` pairs out of the pool.
+    fn take_workers(&mut self, count: usize) -> Vec<(EmailClientStd, EmailClientStd)> {
+        let mut workers = Vec::with_capacity(count);
+        while workers.len() < count {
+            match (self.left.pop(), self.right.pop()) {
+                (Some(l), Some(r)) => workers.push((l, r)),
+                (l, r) => {
+                    self.left.extend(l);
+                    self.right.extend(r);
+                    break;
+                }
+            }
+        }
+        workers
+    }
 }
 
 /// Per-email-hunk outcome; successful `Copy` resolves to `Some(new_id)`,
@@ -200,93 +376,416 @@ impl Pool {
 pub struct HunkOutcome {
     pub hunk: EmailHunk,
     pub result: Result<Option<String>>,
+    /// Transient failures retried before `result` was reached.
+    pub retries: u32,
 }
 
 /// Per-mailbox-hunk outcome.
 pub struct MailboxHunkOutcome {
     pub hunk: MailboxHunk,
     pub result: Result<()>,
+    pub retries: u32,
 }
 
-/// Opens one side's client pool in parallel; the first error propagates
-/// and drops the partial pool.
-fn open_side(config: SideConfig) -> Result<Vec<EmailClientStd>> {
-    let size = resolve_size(&config);
-
-    thread::scope(|scope| -> Result<Vec<EmailClientStd>> {
-        let handles: Vec<_> = (0..size)
+/// Opens one side's client pool: the first connection alone, so a
+/// bad config fails once, the rest in parallel. With `pool-size =
+/// "auto"` connections the server refuses beyond the first (its
+/// connection limit) are only warned about and the pool stays
+/// smaller; with an explicit size the first error propagates and
+/// drops the partial pool.
+fn open_side(config: &SideConfig) -> Result<Vec<EmailClientStd>> {
+    let first = client::open(config.clone())?;
+    let size = resolve_size(config);
+    debug!("opening {size} connection(s)");
+
+    let opened = thread::scope(|scope| -> Result<Vec<Result<EmailClientStd>>> {
+        let handles: Vec<_> = (1..size)
             .map(|_| {
                 let cfg = config.clone();
                 scope.spawn(move || client::open(cfg))
             })
             .collect();
 
-        let mut clients = Vec::with_capacity(size);
+        let mut opened = Vec::with_capacity(size);
         for h in handles {
             match h.join() {
-                Ok(result) => clients.push(result?),
+                Ok(result) => opened.push(result),
                 Err(_) => bail!("Pool open thread panicked"),
             }
         }
-        Ok(clients)
-    })
+        Ok(opened)
+    })?;
+
+    let mut clients = Vec::with_capacity(size);
+    clients.push(first);
+    for result in opened {
+        match result {
+            Ok(client) => clients.push(client),
+            Err(err) if config.pool_size() == PoolSize::Auto => {
+                warn!("skipping pool connection the server refused: {err:#}");
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(clients)
 }
 
-/// Resolves the per-side pool size from config or the per-backend
-/// default (IMAP 8, JMAP 4, m2dir 8); IMAP warns above the soft cap.
+/// Resolves the per-side pool size: an explicit `pool-size` wins
+/// (warned about above [`IMAP_SOFT_LIMIT`] on IMAP), `"auto"` takes
+/// the per-backend default (IMAP 8, JMAP 4, m2dir 8).
 fn resolve_size(config: &SideConfig) -> usize {
-    let default = if config.is_imap() {
-        8
-    } else if config.is_jmap() {
-        4
-    } else {
-        8
-    };
-
-    let requested = config.pool_size().unwrap_or(default).max(1);
-
-    if config.is_imap() && requested > IMAP_SOFT_LIMIT {
-        warn!("imap pool size {requested} exceeds cap {IMAP_SOFT_LIMIT}");
+    match config.pool_size() {
+        PoolSize::Fixed(size) => {
+            if config.is_imap() && size > IMAP_SOFT_LIMIT {
+                warn!("imap pool size {size} exceeds cap {IMAP_SOFT_LIMIT}");
+            }
+            size.max(1)
+        }
+        PoolSize::Auto if config.is_jmap() => 4,
+        PoolSize::Auto => 8,
+    }
+}
+
+fn no_worker_left() -> Error {
+    anyhow!("Every pool connection dropped and could not be reopened")
+}
+
+/// Per-worker connection health check: marks the sides a hunk touched
+/// as stale when it failed on a dropped connection, then reopens them
+/// from the stored [`SideConfig`] before the next attempt.
+struct Healer<'a> {
+    left_config: &'a SideConfig,
+    right_config: &'a SideConfig,
+    /// Mailbox the worker's clients were pre-selected on.
+    mailbox: Option<&'a str>,
+    left_stale: bool,
+    right_stale: bool,
+}
+
+impl<'a> Healer<'a> {
+    fn new(
+        left_config: &'a SideConfig,
+        right_config: &'a SideConfig,
+        mailbox: Option<&'a str>,
+    ) -> Self {
+        Self {
+            left_config,
+            right_config,
+            mailbox,
+            left_stale: false,
+            right_stale: false,
+        }
+    }
+
+    fn is_healthy(&self) -> bool {
+        !self.left_stale && !self.right_stale
+    }
+
+    /// Flags `sides` as stale when `err` means the connection is gone.
+    fn observe(&mut self, err: &Error, sides: &[Side]) {
+        if !retry::is_disconnect(err) {
+            return;
+        }
+        for side in sides {
+            match side {
+                Side::Left => self.left_stale = true,
+                Side::Right => self.right_stale = true,
+            }
+        }
+    }
+
+    /// Reopens every stale side in place; a failure leaves the side
+    /// stale so the next attempt tries again.
+    fn heal(&mut self, left: &mut EmailClientStd, right: &mut EmailClientStd) -> Result<()> {
+        if self.left_stale {
+            *left = self.reconnect(Side::Left)?;
+            self.left_stale = false;
+        }
+        if self.right_stale {
+            *right = self.reconnect(Side::Right)?;
+            self.right_stale = false;
+        }
+        Ok(())
+    }
+
+    fn reconnect(&self, side: Side) -> Result<EmailClientStd> {
+        let config = match side {
+            Side::Left => self.left_config,
+            Side::Right => self.right_config,
+        };
+
+        debug!("{side} connection dropped, reconnecting");
+        #[allow(unused_mut)]
+        let mut client = client::open(config.clone()).context(format!("Reconnect {side} side"))?;
+
+        // NOTE: the mailbox may not exist on this side (e.g. the
+        // APPEND target of a just-created mailbox); a failed SELECT
+        // leaves the client as usable as the initial pre-select did.
+        #[cfg(feature = "imap")]
+        if let Some(mailbox) = self.mailbox {
+            if let Err(err) = client::imap_select(&mut client, mailbox) {
+                debug!("{side} re-select `{mailbox}` after reconnect failed: {err:#}");
+            }
+        }
+
+        Ok(client)
+    }
+}
+
+/// Worker-owned `(left, right)` pair plus the run-wide policies every
+/// request goes through.
+struct Worker<'a> {
+    left: EmailClientStd,
+    right: EmailClientStd,
+    healer: Healer<'a>,
+    retry: &'a RetryConfig,
+    throttles: &'a Throttles,
+    requests: &'a RequestCounter,
+    large_copies: &'a Mutex<()>,
+    guards: WriteGuards,
+}
+
+impl Worker<'_> {
+    /// Runs `op` against the pair: throttled by the `sides` it talks
+    /// to, retrying the failures `retryable` accepts and reconnecting
+    /// the `sides` it dropped.
+    fn attempt<T>(
+        &mut self,
+        sides: &[Side],
+        retryable: fn(&Error) -> bool,
+        mut op: impl FnMut(&mut EmailClientStd, &mut EmailClientStd) -> Result<T>,
+    ) -> (Result<T>, u32) {
+        let Self {
+            left,
+            right,
+            healer,
+            retry,
+            throttles,
+            requests,
+            ..
+        } = self;
+
+        let (result, retries) = retry::with_retry(retry, retryable, || {
+            // NOTE: `sides` is always in left-then-right order, so two
+            // workers never wait on each other's permits.
+            let _permits: Vec<_> = sides
+                .iter()
+                .map(|side| throttles.side(*side).acquire())
+                .collect();
+            healer.heal(left, right).context(NotSent)?;
+            for side in sides {
+                requests.add(*side, 1);
+            }
+            let result = op(left, right);
+            if let Err(err) = &result {
+                healer.observe(err, sides);
+            }
+            for side in sides {
+                throttles.side(*side).observe(&result);
+            }
+            result
+        });
+
+        let result = result.map_err(|err| {
+            if retry::is_over_quota(&err) {
+                err.context("Mailbox over quota, free some space then sync again")
+            } else {
+                err
+            }
+        });
+        (result, retries)
+    }
+
+    /// Copies one message: the fetch is retried like any read, the
+    /// append only when it provably stored nothing.
+    fn copy(&mut self, hunk: &EmailHunk) -> (Result<Option<String>>, u32) {
+        let (source, target) = hunk.copy_sides();
+
+        let (fetched, fetch_retries) = self.attempt(source, is_transient, |l, r| hunk.fetch(l, r));
+        let raw = match fetched {
+            Ok(raw) => raw,
+            Err(err) => return (Err(err), fetch_retries),
+        };
+
+        // NOTE: the body moves into the first append instead of being
+        // cloned per attempt; the rare refused append fetches it again.
+        let requests = self.requests;
+        let mut raw = Some(raw);
+        let (result, append_retries) = self.attempt(target, is_refused, |l, r| {
+            let raw = match raw.take() {
+                Some(raw) => raw,
+                None => {
+                    requests.add(source[0], 1);
+                    hunk.fetch(l, r)?
+                }
+            };
+            hunk.append(l, r, raw)
+        });
+        (result.map(Some), fetch_retries + append_retries)
+    }
+
+    /// Applies one work unit, one outcome per hunk it carries.
+    fn apply(&mut self, work: EmailWork) -> Vec<HunkOutcome> {
+        match work {
+            EmailWork::Single(hunk) if hunk.is_large() => {
+                let gate = self.large_copies;
+                let _gate = gate.lock().unwrap_or_else(PoisonError::into_inner);
+                let (result, retries) = self.copy(&hunk);
+                vec![HunkOutcome {
+                    hunk,
+                    result,
+                    retries,
+                }]
+            }
+            EmailWork::Single(hunk @ EmailHunk::Copy { .. }) => {
+                let (result, retries) = self.copy(&hunk);
+                vec![HunkOutcome {
+                    hunk,
+                    result,
+                    retries,
+                }]
+            }
+            EmailWork::Single(hunk) => {
+                let (checked, check_retries) = match hunk.guarded_write() {
+                    Some((side, mailbox, id)) => self.check(side, mailbox, &[id]),
+                    None => (Ok(Vec::new()), 0),
+                };
+                let (result, retries) = match checked {
+                    Ok(modified) if !modified.is_empty() => {
+                        (Err(Error::new(ModifiedConcurrently)), 0)
+                    }
+                    Ok(_) => self.attempt(hunk.sides(), is_transient, |l, r| hunk.apply(l, r)),
+                    Err(err) => (Err(err), 0),
+                };
+                vec![HunkOutcome {
+                    hunk,
+                    result,
+                    retries: check_retries + retries,
+                }]
+            }
+            EmailWork::Flags(batch) => {
+                let ids: Vec<&str> = batch.hunks.iter().filter_map(EmailHunk::flag_id).collect();
+                let (checked, check_retries) = self.check(batch.side, &batch.mailbox, &ids);
+                let modified = match checked {
+                    Ok(modified) => modified,
+                    Err(err) => return fail_all(batch.hunks, &err, check_retries),
+                };
+                let (result, retries) =
+                    self.attempt(batch.side.as_slice(), is_transient, |l, r| {
+                        batch.apply(l, r, &modified)
+                    });
+                let retries = check_retries + retries;
+                match result {
+                    Ok(()) => batch
+                        .hunks
+                        .into_iter()
+                        .map(|hunk| {
+                            let refused = hunk
+                                .flag_id()
+                                .is_some_and(|id| modified.iter().any(|m| m == id));
+                            let result = if refused {
+                                Err(Error::new(ModifiedConcurrently))
+                            } else {
+                                Ok(None)
+                            };
+                            HunkOutcome {
+                                hunk,
+                                result,
+                                retries,
+                            }
+                        })
+                        .collect(),
+                    Err(err) => fail_all(batch.hunks, &err, retries),
+                }
+            }
+        }
+    }
+
+    /// Checks `ids` against `side`'s [`WriteGuard`], if any; returns
+    /// the ids changed since the listing. The guard is locked for the
+    /// check request only, never across a write or a retry delay.
+    fn check(&mut self, side: Side, mailbox: &str, ids: &[&str]) -> (Result<Vec<String>>, u32) {
+        let Some(guard) = self.guards.side(side) else {
+            return (Ok(Vec::new()), 0);
+        };
+        self.attempt(side.as_slice(), is_transient, |l, r| {
+            let mut guard = guard.lock().unwrap_or_else(PoisonError::into_inner);
+            guard.check(side.client_mut(l, r), mailbox, ids)
+        })
     }
 
-    requested
+    fn into_pair(self) -> (EmailClientStd, EmailClientStd) {
+        (self.left, self.right)
+    }
+}
+
+/// Outcomes for hunks sharing one failed request.
+fn fail_all(hunks: Vec<EmailHunk>, err: &Error, retries: u32) -> Vec<HunkOutcome> {
+    let err = format!("{err:#}");
+    hunks
+        .into_iter()
+        .map(|hunk| HunkOutcome {
+            hunk,
+            result: Err(anyhow!("{err}")),
+            retries,
+        })
+        .collect()
 }
 
-/// One email-hunk worker: drain the queue, apply each hunk against the
-/// `(left, right)` pair, return the pair on exit.
+/// One email-hunk worker: drain the queue, apply each work unit
+/// against the worker pair (throttled, retrying transient failures,
+/// see [`Worker::copy`] for copies, reconnecting dropped clients) and
+/// report one outcome per hunk; returns the pair on exit, or `None`
+/// once it could not be reconnected.
 fn email_worker(
-    mut left: EmailClientStd,
-    mut right: EmailClientStd,
-    mailbox: String,
-    queue: Arc<SegQueue<EmailHunk>>,
+    mut worker: Worker<'_>,
+    queue: Arc<SegQueue<EmailWork>>,
     done_tx: mpsc::Sender<HunkOutcome>,
-) -> (EmailClientStd, EmailClientStd) {
-    while let Some(hunk) = queue.pop() {
-        let result = hunk
-            .apply(&mut left, &mut right)
-            .context(format!("Apply hunk in `{mailbox}`"));
+) -> Option<(EmailClientStd, EmailClientStd)> {
+    'queue: while let Some(work) = queue.pop() {
+        let mailbox = worker.healer.mailbox.unwrap_or_default();
+        for outcome in worker.apply(work) {
+            let outcome = HunkOutcome {
+                result: outcome.result.context(format!("Apply hunk in `{mailbox}`")),
+                ..outcome
+            };
+            if done_tx.send(outcome).is_err() {
+                break 'queue;
+            }
+        }
 
-        if done_tx.send(HunkOutcome { hunk, result }).is_err() {
-            break;
+        if !worker.healer.is_healthy() {
+            warn!("retiring email worker: connection could not be reopened");
+            return None;
         }
     }
-    (left, right)
+    Some(worker.into_pair())
 }
 
 /// Mailbox-hunk counterpart of [`email_worker`].
 fn mailbox_worker(
-    mut left: EmailClientStd,
-    mut right: EmailClientStd,
+    mut worker: Worker<'_>,
     queue: Arc<SegQueue<MailboxHunk>>,
     done_tx: mpsc::Sender<MailboxHunkOutcome>,
-) -> (EmailClientStd, EmailClientStd) {
+) -> Option<(EmailClientStd, EmailClientStd)> {
     while let Some(hunk) = queue.pop() {
-        let result = hunk
-            .apply(&mut left, &mut right)
-            .context("Apply mailbox hunk");
-        if done_tx.send(MailboxHunkOutcome { hunk, result }).is_err() {
+        let (result, retries) = worker.attempt(hunk.sides(), is_transient, |l, r| hunk.apply(l, r));
+        let result = result.context("Apply mailbox hunk");
+
+        let outcome = MailboxHunkOutcome {
+            hunk,
+            result,
+            retries,
+        };
+        if done_tx.send(outcome).is_err() {
             break;
         }
+
+        if !worker.healer.is_healthy() {
+            warn!("retiring mailbox worker: connection could not be reopened");
+            return None;
+        }
     }
-    (left, right)
+    Some(worker.into_pair())
 }
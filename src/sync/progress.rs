@@ -0,0 +1,210 @@
+// This file is part of Neverest, a CLI to synchronize emails.
+//
+// Copyright (C) 2024-2026  soywod <pimalaya.org@posteo.net>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Machine-readable progress (`--progress=jsonl`): a [`SyncObserver`]
+//! writing the stages, mailboxes and hunks of a run as one JSON object
+//! per line, for frontends that cannot read the spinner.
+
+use std::{
+    fmt,
+    io::Write,
+    sync::{Arc, Mutex},
+};
+
+use log::debug;
+use serde::Serialize;
+
+use crate::sync::observer::{HunkProgress, Stage, SyncObserver};
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum ProgressEvent {
+    StageStart {
+        stage: Stage,
+    },
+    StageEnd {
+        stage: Stage,
+        elapsed_ms: u64,
+    },
+    /// `done` mailboxes out of `total` went through `stage`.
+    Mailbox {
+        stage: Stage,
+        mailbox: String,
+        done: usize,
+        total: usize,
+    },
+    /// A hunk came back from the pool.
+    Hunk {
+        #[serde(flatten)]
+        hunk: HunkProgress,
+        percent: usize,
+    },
+}
+
+/// One line of the stream.
+#[derive(Serialize)]
+struct ProgressLine<'a> {
+    account: &'a str,
+    #[serde(flatten)]
+    event: &'a ProgressEvent,
+}
+
+type Sink = Arc<Mutex<dyn Write + Send>>;
+
+/// Where progress events go; silent by default. Clones share the same
+/// sink, so accounts synced in parallel interleave whole lines.
+#[derive(Clone, Default)]
+pub struct Progress {
+    account: Arc<str>,
+    sink: Option<Sink>,
+}
+
+impl Progress {
+    /// Writes every event as a JSON line to `writer`.
+    pub fn jsonl(writer: impl Write + Send + 'static) -> Self {
+        Self {
+            account: Arc::from(""),
+            sink: Some(Arc::new(Mutex::new(writer))),
+        }
+    }
+
+    /// Same sink, with events tagged by `account`.
+    pub fn for_account(&self, account: &str) -> Self {
+        Self {
+            account: Arc::from(account),
+            sink: self.sink.clone(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.sink.is_some()
+    }
+
+    /// Writes `event`; a sink that cannot be written to is only
+    /// logged, it never fails the sync.
+    pub fn emit(&self, event: ProgressEvent) {
+        let Some(sink) = &self.sink else {
+            return;
+        };
+
+        let line = ProgressLine {
+            account: &self.account,
+            event: &event,
+        };
+        let mut line = match serde_json::to_vec(&line) {
+            Ok(line) => line,
+            Err(err) => {
+                debug!("cannot serialize progress event: {err}");
+                return;
+            }
+        };
+        line.push(b'\n');
+
+        let mut sink = sink.lock().unwrap_or_else(|err| err.into_inner());
+        if let Err(err) = sink.write_all(&line).and_then(|()| sink.flush()) {
+            debug!("cannot write progress event: {err}");
+        }
+    }
+}
+
+impl SyncObserver for Progress {
+    fn stage_started(&mut self, stage: Stage, _message: &str) {
+        self.emit(ProgressEvent::StageStart { stage });
+    }
+
+    fn stage_finished(&mut self, stage: Stage, _message: &str, elapsed_ms: u64) {
+        self.emit(ProgressEvent::StageEnd { stage, elapsed_ms });
+    }
+
+    fn mailbox_finished(&mut self, stage: Stage, mailbox: &str, done: usize, total: usize) {
+        self.emit(ProgressEvent::Mailbox {
+            stage,
+            mailbox: mailbox.to_owned(),
+            done,
+            total,
+        });
+    }
+
+    fn hunk_finished(&mut self, hunk: &HunkProgress) {
+        if self.is_enabled() {
+            self.emit(ProgressEvent::Hunk {
+                hunk: hunk.clone(),
+                percent: hunk.percent(),
+            });
+        }
+    }
+}
+
+impl fmt::Debug for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Progress")
+            .field("account", &self.account)
+            .field("enabled", &self.is_enabled())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::observer::HunkStatus;
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn events_are_written_as_tagged_json_lines() {
+        let buffer = Buffer::default();
+        let progress = Progress::jsonl(buffer.clone()).for_account("work");
+
+        let mut progress = progress;
+        progress.stage_started(Stage::MailboxPatch, "Patching mailboxes…");
+        progress.hunk_finished(&HunkProgress {
+            mailbox: "INBOX".into(),
+            hunk: "delete message `1`".into(),
+            status: HunkStatus::Failed,
+            error: Some("boom".into()),
+            applied: 1,
+            total: 4,
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["account"], "work");
+        assert_eq!(lines[0]["event"], "stage-start");
+        assert_eq!(lines[0]["stage"], "mailbox-patch");
+        assert_eq!(lines[1]["event"], "hunk");
+        assert_eq!(lines[1]["status"], "failed");
+        assert_eq!(lines[1]["error"], "boom");
+        assert_eq!(lines[1]["percent"], 25);
+    }
+}
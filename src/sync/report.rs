@@ -18,16 +18,21 @@
 //! End-of-run summary returned by the sync engine; implements `Display`
 //! for the terminal and `Serialize` for `--json`.
 
-use std::fmt;
+use std::{collections::BTreeMap, fmt};
 
-use serde::Serialize;
+use chrono::{DateTime, FixedOffset};
+use io_email::envelope::Envelope;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     side::Side,
-    sync::hunk::{EmailHunk, MailboxHunk},
+    sync::{
+        hunk::{EmailHunk, MailboxHunk},
+        review::Reviewable,
+    },
 };
 
-#[derive(Debug, Default, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct SyncReport {
     pub account: String,
     pub dry_run: bool,
@@ -37,10 +42,314 @@ pub struct SyncReport {
     /// rest skipped).
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub collisions: Vec<MessageCollision>,
+    /// Mailboxes whose message ids were all renewed server-side
+    /// (UIDVALIDITY reset, JMAP mailbox recreated).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub identity_resets: Vec<IdentityReset>,
+    /// Hunks turned down in `--interactive` review, or computed but
+    /// missing from an applied plan.
+    #[serde(default, skip_serializing_if = "Rejected::is_empty")]
+    pub rejected: Rejected,
+    /// Hunks the diff computed but side permissions forbid; the sides
+    /// stay apart on them until the permissions allow it.
+    #[serde(default, skip_serializing_if = "BlockedHunks::is_empty")]
+    pub blocked: BlockedHunks,
+    /// Server pushback met during the run, one entry per side that
+    /// throttled us.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub throttle: Vec<ThrottleReport>,
+    /// `post-sync` and `on-new-message` hooks that failed; the sync
+    /// itself is unaffected.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hooks: Vec<HookFailure>,
+    /// Per-mailbox breakdown of the message patch, sorted by mailbox.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mailboxes: Vec<MailboxStats>,
+    #[serde(default)]
+    pub stats: RunStats,
+    /// Print the evidence behind every hunk reason (`--verbose`);
+    /// the JSON report always carries it.
+    #[serde(skip)]
+    pub verbose: bool,
+}
+
+/// Hook command that could not be run or exited unsuccessfully.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HookFailure {
+    /// Hook name, e.g. `post-sync`.
+    pub hook: String,
+    /// Mailbox and id of the new message, for `on-new-message`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mailbox: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub error: String,
+}
+
+impl fmt::Display for HookFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} hook", self.hook)?;
+        if let (Some(mailbox), Some(id)) = (&self.mailbox, &self.id) {
+            write!(f, " for message `{id}` in `{mailbox}`")?;
+        }
+        write!(f, " failed: {}", self.error)
+    }
+}
+
+/// How hard one side's server pushed back during the run.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ThrottleReport {
+    pub side: Side,
+    /// Pressure signals received (throttling codes, dropped
+    /// connections).
+    pub events: u32,
+    /// Fewest requests allowed in flight at any point.
+    pub lowest_concurrency: usize,
+    /// Longest pause inserted before a request.
+    pub longest_delay_ms: u64,
+}
+
+impl fmt::Display for ThrottleReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            side,
+            events,
+            lowest_concurrency,
+            longest_delay_ms,
+        } = self;
+        write!(
+            f,
+            "{side} server asked to slow down {events} times: concurrency lowered to {lowest_concurrency}, delays up to {longest_delay_ms}ms"
+        )
+    }
+}
+
+/// Where the run spent its time and traffic.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct RunStats {
+    pub timings: StageTimings,
+    /// Message bytes copied each way, from the source sizes; bytes a
+    /// dry run would copy.
+    pub bytes: Transfer,
+    /// Backend calls sent to each side; a batched write counts once.
+    pub requests: SideCounts,
+}
+
+impl fmt::Display for RunStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let StageTimings {
+            probe_ms,
+            list_ms,
+            diff_ms,
+            apply_ms,
+            persist_ms,
+            total_ms,
+        } = self.timings;
+        writeln!(
+            f,
+            " - stages: probe {}, list {}, diff {}, apply {}, persist {} (total {})",
+            format_ms(probe_ms),
+            format_ms(list_ms),
+            format_ms(diff_ms),
+            format_ms(apply_ms),
+            format_ms(persist_ms),
+            format_ms(total_ms),
+        )?;
+        writeln!(f, " - copied: {}", self.bytes)?;
+        write!(
+            f,
+            " - requests: {} to left, {} to right",
+            self.requests.left, self.requests.right
+        )
+    }
+}
+
+/// Wall-clock milliseconds per stage. `list`, `diff` and `apply` add
+/// up every mailbox, and mailboxes overlap across lanes, so their sum
+/// can exceed `total`.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct StageTimings {
+    pub probe_ms: u64,
+    pub list_ms: u64,
+    pub diff_ms: u64,
+    /// Mailbox patch included.
+    pub apply_ms: u64,
+    pub persist_ms: u64,
+    pub total_ms: u64,
+}
+
+/// Bytes per copy direction.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct Transfer {
+    pub left_to_right: u64,
+    pub right_to_left: u64,
+}
+
+impl Transfer {
+    /// Counts `bytes` copied from `source` to the other side.
+    pub fn record(&mut self, source: Side, bytes: u64) {
+        match source {
+            Side::Left => self.left_to_right += bytes,
+            Side::Right => self.right_to_left += bytes,
+        }
+    }
+
+    pub fn add(&mut self, other: Transfer) {
+        self.left_to_right += other.left_to_right;
+        self.right_to_left += other.right_to_left;
+    }
+}
+
+impl fmt::Display for Transfer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} left → right, {} right → left",
+            format_size(self.left_to_right),
+            format_size(self.right_to_left)
+        )
+    }
+}
+
+/// Count per side.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct SideCounts {
+    pub left: u64,
+    pub right: u64,
+}
+
+/// Message patch of one mailbox, in numbers.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct MailboxStats {
+    pub mailbox: String,
+    pub hunks: HunkCounts,
+    /// Hunks that failed to apply.
+    pub errors: usize,
+    pub bytes: Transfer,
+    pub timings: MailboxTimings,
+}
+
+impl MailboxStats {
+    /// Tallies the report `entries` of `mailbox`.
+    pub fn new(
+        mailbox: String,
+        entries: &[PatchEntry<EmailHunk>],
+        timings: MailboxTimings,
+    ) -> Self {
+        let mut stats = Self {
+            mailbox,
+            timings,
+            ..Default::default()
+        };
+        for entry in entries {
+            stats.hunks.record(&entry.hunk);
+            if entry.error.is_some() {
+                stats.errors += 1;
+                continue;
+            }
+            if let (
+                EmailHunk::Copy {
+                    source_side, size, ..
+                },
+                None,
+            ) = (&entry.hunk, &entry.skipped)
+            {
+                stats.bytes.record(*source_side, *size);
+            }
+        }
+        stats
+    }
+}
+
+impl fmt::Display for MailboxStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            mailbox,
+            hunks,
+            errors,
+            bytes,
+            timings,
+        } = self;
+        write!(f, "`{mailbox}`: {hunks}")?;
+        if *errors > 0 {
+            write!(f, " ({errors} failed)")?;
+        }
+        if hunks.copy > 0 {
+            write!(f, "; {bytes}")?;
+        }
+        write!(
+            f,
+            "; listed in {}, diffed in {}, applied in {}",
+            format_ms(timings.list_ms),
+            format_ms(timings.diff_ms),
+            format_ms(timings.apply_ms),
+        )
+    }
+}
+
+/// Hunks per kind.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct HunkCounts {
+    pub copy: usize,
+    pub add_flags: usize,
+    pub remove_flags: usize,
+    pub delete: usize,
+}
+
+impl HunkCounts {
+    pub fn record(&mut self, hunk: &EmailHunk) {
+        match hunk {
+            EmailHunk::Copy { .. } => self.copy += 1,
+            EmailHunk::AddFlags { .. } => self.add_flags += 1,
+            EmailHunk::RemoveFlags { .. } => self.remove_flags += 1,
+            EmailHunk::Delete { .. } => self.delete += 1,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.copy + self.add_flags + self.remove_flags + self.delete
+    }
+}
+
+impl fmt::Display for HunkCounts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kinds = [
+            (self.copy, "copy"),
+            (self.add_flags, "add-flags"),
+            (self.remove_flags, "remove-flags"),
+            (self.delete, "delete"),
+        ];
+        let counts: Vec<String> = kinds
+            .into_iter()
+            .filter(|(n, _)| *n > 0)
+            .map(|(n, kind)| format!("{n} {kind}"))
+            .collect();
+        match counts.as_slice() {
+            [] => write!(f, "no hunks"),
+            counts => write!(f, "{}", counts.join(", ")),
+        }
+    }
+}
+
+/// Wall-clock milliseconds spent on one mailbox.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct MailboxTimings {
+    pub list_ms: u64,
+    pub diff_ms: u64,
+    pub apply_ms: u64,
+}
+
+/// Human-readable duration, e.g. `340ms` or `2.1s`.
+pub fn format_ms(ms: u64) -> String {
+    if ms < 1000 {
+        format!("{ms}ms")
+    } else {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    }
 }
 
 /// One content-key collision group; first id in `ids` is the kept one.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct MessageCollision {
     pub side: Side,
     pub mailbox: String,
@@ -79,7 +388,80 @@ impl fmt::Display for MessageCollision {
     }
 }
 
-#[derive(Debug, Serialize)]
+/// Mailbox whose cached ids went stale all at once; the fresh listing
+/// was re-paired by content key instead of copied again.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct IdentityReset {
+    pub side: Side,
+    pub mailbox: String,
+    /// Cached ids rewritten to their new value.
+    pub rebound: usize,
+}
+
+impl fmt::Display for IdentityReset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            side,
+            mailbox,
+            rebound,
+        } = self;
+        write!(
+            f,
+            "{side} `{mailbox}` renewed every message id (UIDVALIDITY reset or mailbox recreated): {rebound} messages re-paired by content, nothing copied"
+        )
+    }
+}
+
+/// Hunks left unapplied on purpose; their cached baseline is kept, so
+/// the next run proposes them again.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Rejected {
+    pub mailbox: Vec<MailboxHunk>,
+    pub email: Vec<EmailHunk>,
+}
+
+impl Rejected {
+    pub fn len(&self) -> usize {
+        self.mailbox.len() + self.email.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Hunks held back by side permissions, see [`Blocked`].
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct BlockedHunks {
+    pub mailbox: Vec<Blocked<MailboxHunk>>,
+    pub email: Vec<Blocked<EmailHunk>>,
+}
+
+impl BlockedHunks {
+    pub fn len(&self) -> usize {
+        self.mailbox.len() + self.email.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Hunk left out of the patch because a side permission forbids it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Blocked<H> {
+    pub hunk: H,
+    /// Permission that blocked it, e.g. `right.message.delete`.
+    pub permission: String,
+}
+
+impl<H: fmt::Display> fmt::Display for Blocked<H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: blocked by `{}`", self.hunk, self.permission)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
 pub struct PatchOutcome<H> {
     pub patch: Vec<PatchEntry<H>>,
 }
@@ -90,11 +472,31 @@ impl<H> Default for PatchOutcome<H> {
     }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct PatchEntry<H> {
     pub hunk: H,
     /// Formatted apply error (`{e:#}`); `None` on success.
     pub error: Option<String>,
+    /// Transient failures retried before the final outcome.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub retries: u32,
+    /// Copy applied, but its id on the target is unknown (no UIDPLUS
+    /// and not found by search): the message is left untracked.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub unverified: bool,
+    /// Why the hunk was left unapplied on purpose, to be re-diffed
+    /// next run.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<String>,
+    /// Id the copied message got on the target side, when known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_id: Option<String>,
+    /// Envelope of the message the hunk is about, when listed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<MessageDetails>,
+    /// Why the diff emitted the hunk.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<HunkReason>,
 }
 
 impl<H> PatchEntry<H> {
@@ -102,10 +504,160 @@ impl<H> PatchEntry<H> {
         Self {
             hunk,
             error: error.map(|e| format!("{e:#}")),
+            retries: 0,
+            unverified: false,
+            skipped: None,
+            target_id: None,
+            details: None,
+            reason: None,
+        }
+    }
+
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    pub fn unverified(mut self) -> Self {
+        self.unverified = true;
+        self
+    }
+
+    pub fn skipped(mut self, reason: impl Into<String>) -> Self {
+        self.skipped = Some(reason.into());
+        self
+    }
+
+    pub fn landed(mut self, target_id: impl Into<String>) -> Self {
+        self.target_id = Some(target_id.into());
+        self
+    }
+
+    pub fn explained(mut self, details: Option<MessageDetails>, reason: HunkReason) -> Self {
+        self.details = details;
+        self.reason = Some(reason);
+        self
+    }
+}
+
+/// Envelope summary shown next to a message hunk.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct MessageDetails {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
+    pub subject: String,
+    /// First `From:` address, with its display name when set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<DateTime<FixedOffset>>,
+    pub size: u64,
+}
+
+impl From<&Envelope> for MessageDetails {
+    fn from(envelope: &Envelope) -> Self {
+        let from = envelope.from.first().map(|addr| match &addr.name {
+            Some(name) if !name.is_empty() => format!("{name} <{}>", addr.email),
+            _ => addr.email.clone(),
+        });
+        Self {
+            message_id: envelope.message_id.clone(),
+            subject: envelope.subject.clone(),
+            from,
+            date: envelope.date,
+            size: envelope.size,
         }
     }
 }
 
+impl fmt::Display for MessageDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let subject = match self.subject.as_str() {
+            "" => "(no subject)",
+            subject => subject,
+        };
+        write!(f, "\"{subject}\"")?;
+        if let Some(from) = &self.from {
+            write!(f, " from {from}")?;
+        }
+        if let Some(date) = &self.date {
+            write!(f, ", {}", date.format("%Y-%m-%d %H:%M"))?;
+        }
+        write!(f, ", {}", format_size(self.size))
+    }
+}
+
+/// Three-way diff rule that emitted a hunk: a one-line `summary`,
+/// plus the per-side state it was read from.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HunkReason {
+    pub summary: String,
+    /// Live and cached state of both sides, one line each.
+    pub evidence: Vec<String>,
+}
+
+/// Human-readable byte count, e.g. `12.3 KiB`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+impl<H: fmt::Display> fmt::Display for PatchEntry<H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.hunk)?;
+        if self.unverified {
+            write!(f, " (unverified)")?;
+        }
+        if let Some(reason) = &self.skipped {
+            write!(f, " ({reason})")?;
+        }
+        match self.retries {
+            0 => Ok(()),
+            1 => write!(f, " (retried once)"),
+            n => write!(f, " (retried {n} times)"),
+        }
+    }
+}
+
+fn is_zero(n: &u32) -> bool {
+    *n == 0
+}
+
+fn is_false(b: &bool) -> bool {
+    !b
+}
+
+/// Writes `entry` as a list item, followed by its envelope and reason
+/// (and the evidence when `verbose`) on indented lines.
+fn write_entry<H: fmt::Display>(
+    f: &mut fmt::Formatter<'_>,
+    entry: &PatchEntry<H>,
+    verbose: bool,
+) -> fmt::Result {
+    writeln!(f, " - {entry}")?;
+    if let Some(details) = &entry.details {
+        writeln!(f, "   {details}")?;
+    }
+    if let Some(reason) = &entry.reason {
+        writeln!(f, "   {}", reason.summary)?;
+        if verbose {
+            for line in &reason.evidence {
+                writeln!(f, "     {line}")?;
+            }
+        }
+    }
+    Ok(())
+}
+
 impl fmt::Display for SyncReport {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f)?;
@@ -124,29 +676,136 @@ impl fmt::Display for SyncReport {
             .filter(|e| e.error.is_some())
             .count();
         let errors = mailbox_errors + email_errors;
-        let warnings = self.collisions.len();
+        let unverified = self.email.patch.iter().filter(|e| e.unverified).count();
+        let mailbox_skipped = self
+            .mailbox
+            .patch
+            .iter()
+            .filter(|e| e.skipped.is_some())
+            .count();
+        let email_skipped = self
+            .email
+            .patch
+            .iter()
+            .filter(|e| e.skipped.is_some())
+            .count();
+        let skipped = mailbox_skipped + email_skipped;
+        let warnings = self.collisions.len()
+            + self.identity_resets.len()
+            + unverified
+            + skipped
+            + self.hooks.len();
 
         if !self.mailbox.patch.is_empty() {
             writeln!(f, "Mailbox patches ({n}):", n = self.mailbox.patch.len())?;
             for entry in &self.mailbox.patch {
-                writeln!(f, " - {hunk}", hunk = entry.hunk)?;
+                write_entry(f, entry, self.verbose)?;
             }
             writeln!(f)?;
         }
 
         if !self.email.patch.is_empty() {
-            writeln!(f, "Message patches ({n}):", n = self.email.patch.len())?;
+            let mut mailboxes: BTreeMap<&str, Vec<&PatchEntry<EmailHunk>>> = BTreeMap::new();
             for entry in &self.email.patch {
-                writeln!(f, " - {hunk}", hunk = entry.hunk)?;
+                mailboxes
+                    .entry(entry.hunk.mailbox())
+                    .or_default()
+                    .push(entry);
+            }
+            for (mailbox, entries) in mailboxes {
+                writeln!(
+                    f,
+                    "Message patches in `{mailbox}` ({n}):",
+                    n = entries.len()
+                )?;
+                for entry in entries {
+                    write_entry(f, entry, self.verbose)?;
+                }
+                writeln!(f)?;
+            }
+        }
+
+        if !self.rejected.is_empty() {
+            writeln!(f, "Rejected ({n}):", n = self.rejected.len())?;
+            for hunk in &self.rejected.mailbox {
+                writeln!(f, " - {hunk}")?;
+            }
+            for hunk in &self.rejected.email {
+                writeln!(f, " - {hunk}")?;
+            }
+            writeln!(f)?;
+        }
+
+        if !self.blocked.is_empty() {
+            writeln!(f, "Blocked ({n}):", n = self.blocked.len())?;
+            for blocked in &self.blocked.mailbox {
+                writeln!(f, " - {blocked}")?;
+            }
+            for blocked in &self.blocked.email {
+                writeln!(f, " - {blocked}")?;
+            }
+            writeln!(f)?;
+        }
+
+        let busy: Vec<&MailboxStats> = self
+            .mailboxes
+            .iter()
+            .filter(|stats| stats.hunks.total() > 0)
+            .collect();
+        if !busy.is_empty() {
+            writeln!(f, "Mailboxes ({n}):", n = busy.len())?;
+            for stats in busy {
+                writeln!(f, " - {stats}")?;
             }
             writeln!(f)?;
         }
 
+        if !self.throttle.is_empty() {
+            writeln!(f, "Throttling:")?;
+            for throttle in &self.throttle {
+                writeln!(f, " - {throttle}")?;
+            }
+            writeln!(f)?;
+        }
+
+        writeln!(f, "Stats:")?;
+        writeln!(f, "{}", self.stats)?;
+        writeln!(f)?;
+
         if warnings > 0 {
             writeln!(f, "Warnings ({warnings}):")?;
+            for reset in &self.identity_resets {
+                writeln!(f, " - {reset}")?;
+            }
             for c in &self.collisions {
                 writeln!(f, " - {c}")?;
             }
+            for entry in self.mailbox.patch.iter().filter(|e| e.skipped.is_some()) {
+                writeln!(
+                    f,
+                    " - {hunk}: {reason}, will be re-diffed next run",
+                    hunk = entry.hunk,
+                    reason = entry.skipped.as_deref().unwrap_or_default(),
+                )?;
+            }
+            for entry in self.email.patch.iter().filter(|e| e.skipped.is_some()) {
+                writeln!(
+                    f,
+                    " - {hunk}: {reason}, will be re-diffed next run",
+                    hunk = entry.hunk,
+                    reason = entry.skipped.as_deref().unwrap_or_default(),
+                )?;
+            }
+            for entry in self.email.patch.iter().filter(|e| e.unverified) {
+                writeln!(
+                    f,
+                    " - {hunk}: the target returned no id and the copy could not be found, left untracked",
+                    hunk = entry.hunk,
+                )?;
+            }
+            for failure in &self.hooks {
+                writeln!(f, " - {failure}")?;
+            }
             writeln!(f)?;
         }
 
@@ -172,35 +831,163 @@ impl fmt::Display for SyncReport {
         }
 
         let account = &self.account;
-        match (total, errors, warnings, self.dry_run) {
-            (0, 0, 0, _) => write!(f, "Account `{account}` is already in sync"),
-            (0, 0, w, _) => write!(f, "Account `{account}` is already in sync ({w} warnings)"),
-            (n, 0, 0, true) => write!(f, "Account `{account}` would apply {n} hunks"),
-            (n, 0, w, true) => write!(
-                f,
-                "Account `{account}` would apply {n} hunks ({w} warnings)"
-            ),
-            (n, e, 0, true) => write!(
-                f,
-                "Account `{account}` would apply {n} hunks ({e} would fail)"
-            ),
-            (n, e, w, true) => write!(
-                f,
-                "Account `{account}` would apply {n} hunks ({e} would fail, {w} warnings)"
-            ),
-            (n, 0, 0, false) => write!(f, "Account `{account}` synchronized: {n} hunks"),
-            (n, 0, w, false) => write!(
-                f,
-                "Account `{account}` synchronized: {n} hunks, {w} warnings"
-            ),
-            (n, e, 0, false) => write!(
+        if total == 0 && !self.rejected.is_empty() {
+            let rejected = self.rejected.len();
+            write!(
                 f,
-                "Account `{account}` partially synchronized: {n} hunks, {e} errors"
-            ),
-            (n, e, w, false) => write!(
+                "Account `{account}` left unchanged: {rejected} hunks rejected"
+            )?;
+        } else {
+            match (total, errors, warnings, self.dry_run) {
+                (0, 0, 0, _) => write!(f, "Account `{account}` is already in sync"),
+                (0, 0, w, _) => write!(f, "Account `{account}` is already in sync ({w} warnings)"),
+                (n, 0, 0, true) => write!(f, "Account `{account}` would apply {n} hunks"),
+                (n, 0, w, true) => write!(
+                    f,
+                    "Account `{account}` would apply {n} hunks ({w} warnings)"
+                ),
+                (n, e, 0, true) => write!(
+                    f,
+                    "Account `{account}` would apply {n} hunks ({e} would fail)"
+                ),
+                (n, e, w, true) => write!(
+                    f,
+                    "Account `{account}` would apply {n} hunks ({e} would fail, {w} warnings)"
+                ),
+                (n, 0, 0, false) => write!(f, "Account `{account}` synchronized: {n} hunks"),
+                (n, 0, w, false) => write!(
+                    f,
+                    "Account `{account}` synchronized: {n} hunks, {w} warnings"
+                ),
+                (n, e, 0, false) => write!(
+                    f,
+                    "Account `{account}` partially synchronized: {n} hunks, {e} errors"
+                ),
+                (n, e, w, false) => write!(
+                    f,
+                    "Account `{account}` partially synchronized: {n} hunks, {e} errors, {w} warnings"
+                ),
+            }?;
+        }
+
+        match self.blocked.len() {
+            0 => Ok(()),
+            1 => write!(f, "; 1 hunk blocked by permissions"),
+            n => write!(f, "; {n} hunks blocked by permissions"),
+        }
+    }
+}
+
+/// Combined summary of a multi-account `sync` run: one entry per
+/// account, sorted by name.
+#[derive(Debug, Default, Serialize)]
+pub struct MultiSyncReport {
+    pub accounts: Vec<AccountSyncOutcome>,
+}
+
+impl MultiSyncReport {
+    /// Number of accounts whose sync aborted before producing a
+    /// [`SyncReport`].
+    pub fn failed(&self) -> usize {
+        self.accounts.iter().filter(|a| a.error.is_some()).count()
+    }
+}
+
+/// Per-account entry of a [`MultiSyncReport`]; exactly one of `report`
+/// and `error` is set.
+#[derive(Debug, Serialize)]
+pub struct AccountSyncOutcome {
+    pub account: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report: Option<SyncReport>,
+    /// Formatted sync error (`{e:#}`) when the account aborted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl AccountSyncOutcome {
+    pub fn success(account: String, report: SyncReport) -> Self {
+        Self {
+            account,
+            report: Some(report),
+            error: None,
+        }
+    }
+
+    pub fn failure(account: String, error: anyhow::Error) -> Self {
+        Self {
+            account,
+            report: None,
+            error: Some(format!("{error:#}")),
+        }
+    }
+}
+
+impl fmt::Display for MultiSyncReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for outcome in &self.accounts {
+            match (&outcome.report, &outcome.error) {
+                (Some(report), _) => writeln!(f, "{report}")?,
+                (None, error) => {
+                    writeln!(f)?;
+                    writeln!(
+                        f,
+                        "Account `{account}` failed: {err}",
+                        account = outcome.account,
+                        err = error.as_deref().unwrap_or_default(),
+                    )?;
+                }
+            }
+        }
+
+        writeln!(f)?;
+
+        let total = self.accounts.len();
+        match self.failed() {
+            0 => write!(f, "Synchronized {total} accounts"),
+            n => write!(
                 f,
-                "Account `{account}` partially synchronized: {n} hunks, {e} errors, {w} warnings"
+                "Synchronized {ok} of {total} accounts ({n} failed)",
+                ok = total - n
             ),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use anyhow::anyhow;
+
+    use super::*;
+
+    fn copy(source_side: Side, size: u64) -> EmailHunk {
+        EmailHunk::Copy {
+            source_side,
+            target_side: source_side.other(),
+            mailbox: "INBOX".into(),
+            source_id: "1".into(),
+            flags: BTreeSet::new(),
+            size,
+            content_key: 1,
+        }
+    }
+
+    #[test]
+    fn mailbox_stats_only_count_bytes_of_applied_copies() {
+        let entries = [
+            PatchEntry::new(copy(Side::Left, 100), None),
+            PatchEntry::new(copy(Side::Right, 20), None),
+            PatchEntry::new(copy(Side::Left, 1000), Some(anyhow!("dropped"))),
+            PatchEntry::new(copy(Side::Left, 1000), None).skipped("stale"),
+        ];
+
+        let stats = MailboxStats::new("INBOX".into(), &entries, MailboxTimings::default());
+        assert_eq!(stats.hunks.copy, 4);
+        assert_eq!(stats.errors, 1);
+        assert_eq!(stats.bytes.left_to_right, 100);
+        assert_eq!(stats.bytes.right_to_left, 20);
+        assert_eq!(stats.hunks.to_string(), "4 copy");
+    }
+}
@@ -0,0 +1,320 @@
+// This file is part of Neverest, a CLI to synchronize emails.
+//
+// Copyright (C) 2024-2026  soywod <pimalaya.org@posteo.net>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Transient / permanent error classification plus the exponential
+//! backoff loop the pool workers wrap every hunk apply with.
+//!
+//! Most hunks can be sent twice without harm, but a copy cannot: an
+//! APPEND whose response got lost may have stored the message, and
+//! sending it again would duplicate it. Copies are only retried on
+//! [`is_refused`] errors, proving the server wrote nothing.
+
+use std::{fmt, io, thread, time::Duration};
+
+use anyhow::{Error, Result};
+use log::debug;
+
+use crate::{config::RetryConfig, sync::throttle};
+
+/// Response fragments flagging a condition expected to clear on its
+/// own: IMAP response codes (RFC 5530) and the JMAP
+/// `serverUnavailable` error type.
+const TRANSIENT_MARKERS: &[&str] = &["[UNAVAILABLE]", "[INUSE]", "serverUnavailable"];
+
+/// Response fragments of a full mailbox (RFC 5530): no retry or
+/// slowdown makes room, so the hunk fails for good.
+const OVER_QUOTA_MARKERS: &[&str] = &["[OVERQUOTA]", "overQuota"];
+
+/// Response fragments meaning the server closed the session: the IMAP
+/// untagged `BYE` (RFC 9051 §7.1.5) and stream-level close messages.
+const DISCONNECT_MARKERS: &[&str] = &["* BYE", "Connection closed", "connection closed"];
+
+/// Context of a request that failed before reaching the server, e.g.
+/// while reopening a dropped connection.
+#[derive(Debug)]
+pub struct NotSent;
+
+impl fmt::Display for NotSent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "request not sent")
+    }
+}
+
+/// Whether `err` is worth retrying: a dropped connection, a server
+/// asking to slow down, a transient I/O failure anywhere in the
+/// chain, or a server response carrying a transient marker.
+pub fn is_transient(err: &Error) -> bool {
+    if is_over_quota(err) {
+        return false;
+    }
+    if is_disconnect(err) || throttle::is_throttled(err) {
+        return true;
+    }
+
+    let transient_io = err
+        .chain()
+        .filter_map(|cause| cause.downcast_ref::<io::Error>())
+        .any(|err| is_transient_io(err.kind()));
+
+    if transient_io {
+        return true;
+    }
+
+    let message = format!("{err:#}");
+    TRANSIENT_MARKERS
+        .iter()
+        .any(|marker| message.contains(marker))
+}
+
+/// Whether `err` proves the request changed nothing server-side, so
+/// that even a non-idempotent one (an APPEND) can be sent again: it
+/// never left, or the server answered with a transient refusal. A
+/// dropped connection proves nothing, the request may have landed.
+pub fn is_refused(err: &Error) -> bool {
+    if err.downcast_ref::<NotSent>().is_some() {
+        return true;
+    }
+    if is_disconnect(err) || is_over_quota(err) {
+        return false;
+    }
+
+    let message = format!("{err:#}");
+    throttle::is_throttled(err)
+        || TRANSIENT_MARKERS
+            .iter()
+            .any(|marker| message.contains(marker))
+}
+
+/// Whether `err` is the server refusing a write for lack of space
+/// (IMAP `[OVERQUOTA]`, JMAP `overQuota`).
+pub fn is_over_quota(err: &Error) -> bool {
+    let message = format!("{err:#}");
+    OVER_QUOTA_MARKERS
+        .iter()
+        .any(|marker| message.contains(marker))
+}
+
+/// Whether `err` means the underlying connection is gone and the
+/// client must be reopened before it can serve another request.
+pub fn is_disconnect(err: &Error) -> bool {
+    let disconnect_io = err
+        .chain()
+        .filter_map(|cause| cause.downcast_ref::<io::Error>())
+        .any(|err| is_disconnect_io(err.kind()));
+
+    if disconnect_io {
+        return true;
+    }
+
+    let message = format!("{err:#}");
+    DISCONNECT_MARKERS
+        .iter()
+        .any(|marker| message.contains(marker))
+}
+
+fn is_disconnect_io(kind: io::ErrorKind) -> bool {
+    use io::ErrorKind::*;
+
+    matches!(
+        kind,
+        ConnectionReset | ConnectionAborted | NotConnected | BrokenPipe | UnexpectedEof
+    )
+}
+
+fn is_transient_io(kind: io::ErrorKind) -> bool {
+    use io::ErrorKind::*;
+
+    matches!(
+        kind,
+        ConnectionReset
+            | ConnectionAborted
+            | ConnectionRefused
+            | NotConnected
+            | BrokenPipe
+            | TimedOut
+            | Interrupted
+            | WouldBlock
+            | UnexpectedEof
+    )
+}
+
+/// Delay to wait before retry number `retry` (1-based): doubles from
+/// `initial-delay-ms`, capped at `max-delay-ms`.
+pub fn backoff(config: &RetryConfig, retry: u32) -> Duration {
+    let factor = 1u64
+        .checked_shl(retry.saturating_sub(1))
+        .unwrap_or(u64::MAX);
+    let delay = config.initial_delay_ms.saturating_mul(factor);
+    Duration::from_millis(delay.min(config.max_delay_ms))
+}
+
+/// Runs `op` until it succeeds, fails with an error `retryable`
+/// rejects (usually [`is_transient`], or [`is_refused`] for
+/// non-idempotent requests) or exhausts `max-attempts`; returns the
+/// last result plus the retry count.
+pub fn with_retry<T>(
+    config: &RetryConfig,
+    retryable: fn(&Error) -> bool,
+    mut op: impl FnMut() -> Result<T>,
+) -> (Result<T>, u32) {
+    let mut retries = 0;
+
+    loop {
+        match op() {
+            Err(err) if retries + 1 < config.max_attempts && retryable(&err) => {
+                retries += 1;
+                let delay = backoff(config, retries);
+                debug!("transient failure, retry {retries} in {delay:?}: {err:#}");
+                thread::sleep(delay);
+            }
+            result => return (result, retries),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::{Context, anyhow};
+
+    use super::*;
+
+    fn config() -> RetryConfig {
+        RetryConfig {
+            max_attempts: 3,
+            initial_delay_ms: 0,
+            max_delay_ms: 0,
+        }
+    }
+
+    #[test]
+    fn io_error_deep_in_chain_is_transient() {
+        let err = Err::<(), _>(io::Error::from(io::ErrorKind::ConnectionReset))
+            .context("Read IMAP response")
+            .context("Apply hunk in `INBOX`")
+            .unwrap_err();
+        assert!(is_transient(&err));
+    }
+
+    #[test]
+    fn imap_unavailable_code_is_transient() {
+        let err = anyhow!("NO [UNAVAILABLE] Backend temporarily down");
+        assert!(is_transient(&err));
+    }
+
+    #[test]
+    fn other_failures_are_permanent() {
+        assert!(!is_transient(&anyhow!("NO [NONEXISTENT] No such mailbox")));
+        assert!(!is_transient(&anyhow!(io::Error::from(
+            io::ErrorKind::PermissionDenied
+        ))));
+    }
+
+    #[test]
+    fn over_quota_is_never_retried() {
+        let err = anyhow!("NO [OVERQUOTA] Mailbox full");
+        assert!(is_over_quota(&err));
+        assert!(!is_transient(&err));
+        assert!(!is_refused(&err));
+        assert!(!is_transient(&anyhow!("JMAP error: overQuota")));
+    }
+
+    #[test]
+    fn only_refusals_and_unsent_requests_are_refused() {
+        assert!(is_refused(&anyhow!("NO [UNAVAILABLE] try later")));
+        assert!(is_refused(&anyhow!("NO [THROTTLED] Slow down")));
+        assert!(is_refused(&anyhow!("connection refused").context(NotSent)));
+        assert!(!is_refused(&anyhow!("* BYE [UNAVAILABLE] shutting down")));
+        assert!(!is_refused(&anyhow!(io::Error::from(
+            io::ErrorKind::ConnectionReset
+        ))));
+        assert!(!is_refused(&anyhow!("NO [NONEXISTENT] gone")));
+    }
+
+    #[test]
+    fn non_idempotent_requests_are_not_retried_after_a_disconnect() {
+        let mut calls = 0;
+        let (result, retries) = with_retry(&config(), is_refused, || -> Result<()> {
+            calls += 1;
+            Err(anyhow!(io::Error::from(io::ErrorKind::UnexpectedEof)))
+        });
+        assert!(result.is_err());
+        assert_eq!((calls, retries), (1, 0));
+    }
+
+    #[test]
+    fn bye_and_broken_pipe_are_disconnects() {
+        assert!(is_disconnect(&anyhow!("* BYE Idle timeout")));
+        assert!(is_disconnect(&anyhow!(io::Error::from(
+            io::ErrorKind::BrokenPipe
+        ))));
+        assert!(!is_disconnect(&anyhow!("NO [UNAVAILABLE] try later")));
+        assert!(is_transient(&anyhow!("* BYE Server shutting down")));
+    }
+
+    #[test]
+    fn backoff_doubles_then_caps() {
+        let config = RetryConfig {
+            max_attempts: 10,
+            initial_delay_ms: 100,
+            max_delay_ms: 1_000,
+        };
+        assert_eq!(backoff(&config, 1), Duration::from_millis(100));
+        assert_eq!(backoff(&config, 2), Duration::from_millis(200));
+        assert_eq!(backoff(&config, 4), Duration::from_millis(800));
+        assert_eq!(backoff(&config, 5), Duration::from_millis(1_000));
+        assert_eq!(backoff(&config, 80), Duration::from_millis(1_000));
+    }
+
+    #[test]
+    fn with_retry_stops_after_max_attempts() {
+        let mut calls = 0;
+        let (result, retries) = with_retry(&config(), is_transient, || -> Result<()> {
+            calls += 1;
+            Err(anyhow!("NO [UNAVAILABLE] try later"))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 3);
+        assert_eq!(retries, 2);
+    }
+
+    #[test]
+    fn with_retry_does_not_retry_permanent_failures() {
+        let mut calls = 0;
+        let (result, retries) = with_retry(&config(), is_transient, || -> Result<()> {
+            calls += 1;
+            Err(anyhow!("NO [NONEXISTENT] gone"))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+        assert_eq!(retries, 0);
+    }
+
+    #[test]
+    fn with_retry_recovers_from_transient_failure() {
+        let mut calls = 0;
+        let (result, retries) = with_retry(&config(), is_transient, || {
+            calls += 1;
+            if calls == 1 {
+                Err(anyhow!("NO [INUSE] mailbox locked"))
+            } else {
+                Ok(42)
+            }
+        });
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(retries, 1);
+    }
+}
@@ -0,0 +1,158 @@
+// This file is part of Neverest, a CLI to synchronize emails.
+//
+// Copyright (C) 2024-2026  soywod <pimalaya.org@posteo.net>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Review hook of `sync --interactive`: the computed patches are handed
+//! to [`SyncObserver::review`], and only approved hunks reach the pool.
+
+use std::fmt;
+
+use anyhow::{Result, bail};
+
+use crate::sync::{
+    hunk::{EmailHunk, MailboxHunk},
+    observer::SyncObserver,
+};
+
+/// One hunk as shown to the reviewer.
+#[derive(Clone, Debug)]
+pub struct ReviewItem {
+    /// Mailbox the hunk is about.
+    pub mailbox: String,
+    /// Hunk kind (`create`, `copy`, `delete`…); items sharing it can be
+    /// approved or rejected at once.
+    pub category: &'static str,
+    pub label: String,
+}
+
+/// Hunk that can be put up for review.
+pub trait Reviewable: fmt::Display {
+    fn mailbox(&self) -> &str;
+    fn category(&self) -> &'static str;
+}
+
+impl Reviewable for MailboxHunk {
+    fn mailbox(&self) -> &str {
+        match self {
+            Self::Create { mailbox, .. } | Self::Delete { mailbox, .. } => mailbox,
+        }
+    }
+
+    fn category(&self) -> &'static str {
+        match self {
+            Self::Create { .. } => "create",
+            Self::Delete { .. } => "delete",
+        }
+    }
+}
+
+impl Reviewable for EmailHunk {
+    fn mailbox(&self) -> &str {
+        match self {
+            Self::Copy { mailbox, .. }
+            | Self::AddFlags { mailbox, .. }
+            | Self::RemoveFlags { mailbox, .. }
+            | Self::Delete { mailbox, .. } => mailbox,
+        }
+    }
+
+    fn category(&self) -> &'static str {
+        match self {
+            Self::Copy { .. } => "copy",
+            Self::AddFlags { .. } => "add-flags",
+            Self::RemoveFlags { .. } => "remove-flags",
+            Self::Delete { .. } => "delete",
+        }
+    }
+}
+
+/// Puts `hunks` up for review; returns `(approved, rejected)`, both
+/// in their original order.
+pub fn review_hunks<H: Reviewable>(
+    observer: &mut dyn SyncObserver,
+    title: &str,
+    hunks: Vec<H>,
+) -> Result<(Vec<H>, Vec<H>)> {
+    if hunks.is_empty() {
+        return Ok((hunks, Vec::new()));
+    }
+
+    let items: Vec<ReviewItem> = hunks
+        .iter()
+        .map(|hunk| ReviewItem {
+            mailbox: hunk.mailbox().to_owned(),
+            category: hunk.category(),
+            label: hunk.to_string(),
+        })
+        .collect();
+    let approvals = observer.review(title, &items)?;
+    if approvals.len() != hunks.len() {
+        bail!(
+            "Review returned {} decisions for {} hunks",
+            approvals.len(),
+            hunks.len()
+        );
+    }
+
+    let mut approved = Vec::new();
+    let mut rejected = Vec::new();
+    for (hunk, ok) in hunks.into_iter().zip(approvals) {
+        if ok {
+            approved.push(hunk);
+        } else {
+            rejected.push(hunk);
+        }
+    }
+    Ok((approved, rejected))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::side::Side;
+
+    struct RejectDeletes;
+
+    impl SyncObserver for RejectDeletes {
+        fn review(&mut self, _title: &str, items: &[ReviewItem]) -> Result<Vec<bool>> {
+            Ok(items.iter().map(|item| item.category != "delete").collect())
+        }
+    }
+
+    #[test]
+    fn rejected_hunks_are_split_off_in_order() {
+        let hunks = vec![
+            MailboxHunk::Create {
+                side: Side::Right,
+                mailbox: "Archive".into(),
+            },
+            MailboxHunk::Delete {
+                side: Side::Left,
+                mailbox: "Old".into(),
+            },
+            MailboxHunk::Create {
+                side: Side::Left,
+                mailbox: "Sent".into(),
+            },
+        ];
+
+        let (approved, rejected) = review_hunks(&mut RejectDeletes, "Mailboxes", hunks).unwrap();
+        let approved: Vec<&str> = approved.iter().map(Reviewable::mailbox).collect();
+        let rejected: Vec<&str> = rejected.iter().map(Reviewable::mailbox).collect();
+        assert_eq!(approved, vec!["Archive", "Sent"]);
+        assert_eq!(rejected, vec!["Old"]);
+    }
+}
@@ -0,0 +1,57 @@
+// This file is part of Neverest, a CLI to synchronize emails.
+//
+// Copyright (C) 2024-2026  soywod <pimalaya.org@posteo.net>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Run-wide measurements behind the `stats` of the report: backend
+//! calls per side and stage timings.
+
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Instant,
+};
+
+use crate::{side::Side, sync::report::SideCounts};
+
+/// Backend calls sent to each side over the run (listings, diffs,
+/// writes, lookups; a batched write counts once). Shared by every
+/// lane, like the [`Throttles`](crate::sync::throttle::Throttles).
+#[derive(Debug, Default)]
+pub struct RequestCounter {
+    left: AtomicU64,
+    right: AtomicU64,
+}
+
+impl RequestCounter {
+    pub fn add(&self, side: Side, requests: u64) {
+        let counter = match side {
+            Side::Left => &self.left,
+            Side::Right => &self.right,
+        };
+        counter.fetch_add(requests, Ordering::Relaxed);
+    }
+
+    pub fn report(&self) -> SideCounts {
+        SideCounts {
+            left: self.left.load(Ordering::Relaxed),
+            right: self.right.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Milliseconds elapsed since `start`.
+pub fn elapsed_ms(start: Instant) -> u64 {
+    start.elapsed().as_millis().try_into().unwrap_or(u64::MAX)
+}
@@ -19,59 +19,77 @@
 //! message patch over both sides against the cached snapshot.
 
 use std::{
-    collections::{BTreeSet, HashSet},
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt, mem,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc,
+    },
     thread,
+    time::Instant,
 };
 
-use anyhow::{Result, anyhow};
+use anyhow::{Context, Result, anyhow, bail};
+use crossbeam_queue::SegQueue;
 use io_email::{
     client::{EmailClientStd, EmailClientStdError},
-    envelope::EnvelopeDiff,
+    envelope::{Envelope, EnvelopeDiff},
     mailbox::MailboxDiff,
 };
-use log::{debug, warn};
-use pimalaya_cli::spinner::Spinner;
+use log::{debug, trace, warn};
 
 use crate::{
-    config::{AccountConfig, MailboxFilter},
+    client::{self, WriteGuard},
+    config::{AccountConfig, MailboxFilter, SidePermissions},
     side::Side,
     sync::{
-        cache::{CacheSnapshot, MessageEntry},
+        cache::{CacheSnapshot, MessageEntry, MessageSnapshots, StatTokens},
         diff::{
-            EnvelopePairs, diff_mailboxes, diff_messages, filter_mailboxes, message_map,
-            pairs_from_delta, pairs_from_envelopes, pairs_to_snapshot,
+            EnvelopePairs, MessageMap, detect_identity_reset, diff_mailboxes, diff_messages,
+            explain_mailbox, explain_message, filter_mailboxes, message_key, message_map,
+            pairs_from_delta, pairs_from_envelopes, pairs_to_snapshot, repair_ids,
         },
-        hunk::{EmailHunk, MailboxHunk},
-        pool::{HunkOutcome, MailboxHunkOutcome, Pool},
-        report::{PatchEntry, SyncReport},
+        history, hook,
+        hunk::{EmailHunk, MailboxHunk, ModifiedConcurrently},
+        observer::{HunkProgress, HunkStatus, Stage, SyncObserver},
+        plan::{self, Checkpoint, MailboxPlan, Plan},
+        pool::{HunkOutcome, MailboxHunkOutcome, Pool, WriteGuards},
+        report::{
+            Blocked, IdentityReset, MailboxStats, MailboxTimings, MessageCollision, MessageDetails,
+            PatchEntry, SyncReport,
+        },
+        retry,
+        review::{self, Reviewable},
+        stats::{RequestCounter, elapsed_ms},
+        throttle::Throttle,
     },
 };
 
-/// Issues a SELECT on `client` when its backend is IMAP, otherwise a
-/// no-op; primes workers for an `auto_select=false` hunk batch.
-#[cfg(feature = "imap")]
-fn imap_select(client: &mut EmailClientStd, mailbox: &str) -> Result<()> {
-    if let Some(imap) = client.as_imap_mut() {
-        imap.select(mailbox.to_owned().try_into()?)?;
-    }
-    Ok(())
-}
-
 /// Probes the per-side mailbox set; uses `diff_mailboxes` when supported
 /// and falls back to a full `list_mailboxes` otherwise.
 fn probe_side_mailboxes(
     client: &mut EmailClientStd,
     side: Side,
     snapshot: &CacheSnapshot,
+    requests: &RequestCounter,
+    throttle: &Throttle,
 ) -> Result<(HashSet<String>, Option<Vec<u8>>)> {
     let cached = snapshot.mailbox_state(side);
+    requests.add(side, 1);
 
-    let (unchanged, new_state) = match client.diff_mailboxes(cached) {
+    let diff = throttle.run(|| Ok(client.diff_mailboxes(cached)?));
+    let (unchanged, new_state) = match diff {
         Ok(MailboxDiff::Unchanged { new_state }) => (true, Some(new_state)),
         Ok(MailboxDiff::Changed { new_state }) => (false, new_state),
-        Err(EmailClientStdError::UnsupportedOperation) => (false, None),
         Err(err) => {
-            warn!("{side} diff_mailboxes failed: {err:#}");
+            let unsupported = matches!(
+                err.downcast_ref::<EmailClientStdError>(),
+                Some(EmailClientStdError::UnsupportedOperation),
+            );
+            if !unsupported {
+                warn!("{side} diff_mailboxes failed: {err:#}");
+            }
             (false, None)
         }
     };
@@ -81,8 +99,9 @@ fn probe_side_mailboxes(
         snapshot.mailbox_names(side)
     } else {
         debug!("listing {side} mailboxes");
-        client
-            .list_mailboxes(false)?
+        requests.add(side, 1);
+        throttle
+            .run(|| Ok(client.list_mailboxes(false)?))?
             .into_iter()
             .map(|m| m.name)
             .collect()
@@ -91,24 +110,107 @@ fn probe_side_mailboxes(
     Ok((mailboxes, new_state))
 }
 
-/// Resolves the envelope set for `(side, mailbox)`; uses the incremental
-/// diff fast path when available, otherwise falls back to a full
-/// `list_envelopes`.
+/// Envelope set of one side of a mailbox, plus what the next run
+/// needs to diff against it.
+#[derive(Default)]
+struct SideListing {
+    pairs: EnvelopePairs,
+    state: Option<Vec<u8>>,
+    /// m2dir sidecar tokens, by id.
+    tokens: StatTokens,
+    /// Precondition of this side's flag and delete writes.
+    guard: Option<WriteGuard>,
+    /// IMAP UIDVALIDITY the listing was taken under.
+    uid_validity: Option<u32>,
+    /// UIDVALIDITY moved since the cached listing: every cached id is
+    /// stale, so this listing is a full one.
+    reset: bool,
+}
+
+/// Lists `(side, mailbox)` over the side's lane `clients`, in full
+/// when its UIDVALIDITY moved since the cached listing, and attaches
+/// the [`WriteGuard`] its hunks will be written under.
 fn fetch_side_envelopes(
-    client: &mut EmailClientStd,
+    clients: &mut [EmailClientStd],
     side: Side,
     mailbox: &str,
     snapshot: &CacheSnapshot,
-) -> Result<(EnvelopePairs, Option<Vec<u8>>)> {
-    let diff = resolve_diff(client, side, mailbox, snapshot);
+    requests: &RequestCounter,
+    throttle: &Throttle,
+    progress: &(dyn Fn(MailboxProgress) + Sync),
+) -> Result<SideListing> {
+    let Some(client) = clients.first_mut() else {
+        bail!("No {side} connection left to list `{mailbox}`");
+    };
+    requests.add(side, imap_requests(client));
+    let uid_validity = throttle
+        .run(|| imap_checkpoint(client, mailbox))
+        .unwrap_or_else(|err| {
+            warn!("{side} SELECT `{mailbox}` failed: {err:#}");
+            None
+        });
+
+    let cached = snapshot.uid_validity(side, mailbox);
+    let reset =
+        matches!((cached, uid_validity), (Some(cached), Some(current)) if cached != current);
+    let mut listing = if reset {
+        warn!("{side} `{mailbox}` UIDVALIDITY changed, listing every message again");
+        let msgs = list_side_envelopes(
+            clients, side, mailbox, snapshot, requests, throttle, progress,
+        )?;
+        SideListing {
+            pairs: pairs_from_envelopes(msgs),
+            ..Default::default()
+        }
+    } else {
+        diff_side_envelopes(
+            clients, side, mailbox, snapshot, requests, throttle, progress,
+        )?
+    };
+    listing.uid_validity = uid_validity;
+    listing.reset = reset;
+    // NOTE: m2dir states are sidecar tokens, not an envelope-diff
+    // state the guard can check from.
+    #[cfg(not(feature = "m2dir"))]
+    let guarded = true;
+    #[cfg(feature = "m2dir")]
+    let guarded = !clients
+        .first()
+        .is_some_and(|client| client.as_m2dir().is_some());
+    if guarded {
+        listing.guard = listing.state.clone().map(WriteGuard::new);
+    }
+    Ok(listing)
+}
+
+/// Resolves the envelope set for `(side, mailbox)` over the side's
+/// lane `clients`; uses the incremental diff fast path when available,
+/// otherwise falls back to a full listing.
+fn diff_side_envelopes(
+    clients: &mut [EmailClientStd],
+    side: Side,
+    mailbox: &str,
+    snapshot: &CacheSnapshot,
+    requests: &RequestCounter,
+    throttle: &Throttle,
+    progress: &(dyn Fn(MailboxProgress) + Sync),
+) -> Result<SideListing> {
+    let Some(client) = clients.first_mut() else {
+        bail!("No {side} connection left to list `{mailbox}`");
+    };
+    requests.add(side, 1);
+    let diff = throttle.run(|| resolve_diff(client, side, mailbox, snapshot));
 
     match diff {
-        Ok(EnvelopeDiff::Incremental {
-            new_state,
-            flag_updates,
-            new_envelopes,
-            vanished_ids,
-        }) => {
+        Ok((
+            EnvelopeDiff::Incremental {
+                new_state,
+                flag_updates,
+                new_envelopes,
+                vanished_ids,
+            },
+            tokens,
+        )) => {
             debug!(
                 "{side} `{mailbox}`: {}+ {}~ {}- (delta)",
                 new_envelopes.len(),
@@ -121,12 +223,28 @@ fn fetch_side_envelopes(
                 .unwrap_or_default();
             let vanished: HashSet<String> = vanished_ids.into_iter().collect();
             let pairs = pairs_from_delta(&prev, flag_updates, new_envelopes, vanished);
-            let captured = (!new_state.is_empty()).then_some(new_state);
-            Ok((pairs, captured))
+            let state = (!new_state.is_empty()).then_some(new_state);
+            Ok(SideListing {
+                pairs,
+                state,
+                tokens,
+                ..Default::default()
+            })
         }
-        Ok(EnvelopeDiff::FullListRequired { new_state }) => {
-            let msgs = client.list_envelopes(mailbox, None, None, false)?;
-            Ok((pairs_from_envelopes(msgs), new_state))
+        // TODO: IMAP servers without QRESYNC land here on every run.
+        // Diff them from HIGHESTMODSEQ (`CHANGEDSINCE`) or from
+        // UIDNEXT plus a `UID FETCH 1:* FLAGS` UID set once `io-imap`
+        // exposes those commands.
+        Ok((EnvelopeDiff::FullListRequired { new_state }, tokens)) => {
+            let msgs = list_side_envelopes(
+                clients, side, mailbox, snapshot, requests, throttle, progress,
+            )?;
+            Ok(SideListing {
+                pairs: pairs_from_envelopes(msgs),
+                state: new_state,
+                tokens,
+                ..Default::default()
+            })
         }
         Err(err) => {
             let unsupported = matches!(
@@ -136,12 +254,218 @@ fn fetch_side_envelopes(
             if !unsupported {
                 warn!("{side} diff_envelopes `{mailbox}` failed: {err:#}");
             }
-            let msgs = client.list_envelopes(mailbox, None, None, false)?;
-            Ok((pairs_from_envelopes(msgs), None))
+            let msgs = list_side_envelopes(
+                clients, side, mailbox, snapshot, requests, throttle, progress,
+            )?;
+            Ok(SideListing {
+                pairs: pairs_from_envelopes(msgs),
+                ..Default::default()
+            })
         }
     }
 }
 
+/// Full listing of `(side, mailbox)`. First syncs of IMAP mailboxes
+/// (no cached messages, several connections at hand) are split into
+/// pages listed by every lane client at once; otherwise a single
+/// `list_envelopes` on the first client.
+///
+/// Pages are positional, so a message expunged or added mid-listing
+/// shifts the next pages and can be skipped. The paged listing is only
+/// kept when the SELECT message count is the same before and after it
+/// and the pages line up (see [`merge_pages`]); otherwise, or when a
+/// page request fails, the mailbox is listed again in one go.
+fn list_side_envelopes(
+    clients: &mut [EmailClientStd],
+    side: Side,
+    mailbox: &str,
+    snapshot: &CacheSnapshot,
+    requests: &RequestCounter,
+    throttle: &Throttle,
+    progress: &(dyn Fn(MailboxProgress) + Sync),
+) -> Result<Vec<Envelope>> {
+    let first_sync = snapshot.messages(side, mailbox).is_none();
+    if first_sync && clients.len() > 1 {
+        if let Some(exists) = listing_count(clients, side, mailbox, requests) {
+            let paged = select_lane(clients, side, mailbox, requests).and_then(|()| {
+                list_envelopes_chunked(clients, side, mailbox, requests, throttle, progress)
+            });
+            let merged = paged.and_then(|pages| {
+                let settled = listing_count(clients, side, mailbox, requests);
+                if settled != Some(exists) {
+                    bail!("message count moved from {exists} to {settled:?}");
+                }
+                merge_pages(pages, LIST_PAGE_SIZE, exists)
+            });
+            match merged {
+                Ok(msgs) => return Ok(msgs),
+                Err(err) => warn!(
+                    "{side} `{mailbox}` paged listing discarded, listing it again in one go: {err:#}"
+                ),
+            }
+        }
+    }
+
+    let Some(client) = clients.first_mut() else {
+        bail!("No {side} connection left to list `{mailbox}`");
+    };
+    requests.add(side, 1);
+    throttle.run(|| Ok(client.list_envelopes(mailbox, None, None, false)?))
+}
+
+/// Message count of `mailbox` from an IMAP SELECT on the first client;
+/// `None` on other backends, which report no count to check a paged
+/// listing against.
+#[cfg_attr(not(feature = "imap"), allow(unused_variables))]
+fn listing_count(
+    clients: &mut [EmailClientStd],
+    side: Side,
+    mailbox: &str,
+    requests: &RequestCounter,
+) -> Option<u32> {
+    #[cfg(feature = "imap")]
+    if let Some(client) = clients.first_mut() {
+        requests.add(side, imap_requests(client));
+        match client::imap_select_status(client, mailbox) {
+            Ok(selected) => return selected.map(|selected| selected.exists),
+            Err(err) => warn!("{side} SELECT `{mailbox}` failed: {err:#}"),
+        }
+    }
+    None
+}
+
+/// SELECTs `mailbox` on the lane clients [`listing_count`] left
+/// alone, so every page request reads the same mailbox.
+fn select_lane(
+    clients: &mut [EmailClientStd],
+    side: Side,
+    mailbox: &str,
+    requests: &RequestCounter,
+) -> Result<()> {
+    for client in clients.iter_mut().skip(1) {
+        requests.add(side, imap_requests(client));
+        client::imap_select(client, mailbox)
+            .context(format!("Select {side} mailbox `{mailbox}` for paging"))?;
+    }
+    Ok(())
+}
+
+/// Lists `mailbox` page by page across `clients`: each client pulls
+/// the next page number off a shared counter until a page comes back
+/// short. Returns the `(page, envelopes)` pulled, unordered.
+fn list_envelopes_chunked(
+    clients: &mut [EmailClientStd],
+    side: Side,
+    mailbox: &str,
+    requests: &RequestCounter,
+    throttle: &Throttle,
+    progress: &(dyn Fn(MailboxProgress) + Sync),
+) -> Result<Vec<(usize, Vec<Envelope>)>> {
+    let next_page = AtomicUsize::new(0);
+    let last_page = AtomicUsize::new(usize::MAX);
+    let listed = AtomicUsize::new(0);
+
+    let pages = thread::scope(|scope| -> Result<Vec<(usize, Vec<Envelope>)>> {
+        let handles: Vec<_> = clients
+            .iter_mut()
+            .map(|client| {
+                let (next_page, last_page, listed) = (&next_page, &last_page, &listed);
+                scope.spawn(move || -> Result<Vec<(usize, Vec<Envelope>)>> {
+                    let mut pages = Vec::new();
+                    loop {
+                        let page = next_page.fetch_add(1, Ordering::Relaxed);
+                        if page > last_page.load(Ordering::Relaxed) {
+                            break;
+                        }
+                        requests.add(side, 1);
+                        let envelopes =
+                            throttle.run(|| {
+                                let page = Some(page);
+                                Ok(client.list_envelopes(
+                                    mailbox,
+                                    Some(LIST_PAGE_SIZE),
+                                    page,
+                                    false,
+                                )?)
+                            })?;
+                        if envelopes.len() < LIST_PAGE_SIZE {
+                            last_page.fetch_min(page, Ordering::Relaxed);
+                        }
+                        let n = envelopes.len();
+                        let envelopes_listed = listed.fetch_add(n, Ordering::Relaxed) + n;
+                        trace!("{side} `{mailbox}` page {page}: {n} envelopes");
+                        progress(MailboxProgress::Listed {
+                            side,
+                            envelopes: envelopes_listed,
+                        });
+                        pages.push((page, envelopes));
+                    }
+                    Ok(pages)
+                })
+            })
+            .collect();
+
+        let mut pages = Vec::new();
+        for handle in handles {
+            let worker_pages = handle
+                .join()
+                .map_err(|_| anyhow!("Envelope page worker panicked"))??;
+            pages.extend(worker_pages);
+        }
+        Ok(pages)
+    })?;
+
+    debug!(
+        "{side} `{mailbox}`: listed {} pages over {} connections",
+        pages.len(),
+        clients.len()
+    );
+    Ok(pages)
+}
+
+/// Merges listed pages in order, checking they tile the mailbox: page
+/// numbers run from 0 without gaps, every page but the last is full,
+/// no id shows up twice and the total is `exists`. Pages listed under
+/// another page base than the 0-based one assumed here overlap or
+/// leave a short first page, and fail these checks.
+fn merge_pages(
+    mut pages: Vec<(usize, Vec<Envelope>)>,
+    page_size: usize,
+    exists: u32,
+) -> Result<Vec<Envelope>> {
+    pages.sort_by_key(|(page, _)| *page);
+    // NOTE: workers racing past the end pull empty pages.
+    while pages.len() > 1 && pages.last().is_some_and(|(_, page)| page.is_empty()) {
+        pages.pop();
+    }
+
+    let last = pages.len().saturating_sub(1);
+    let mut seen = HashSet::new();
+    let mut msgs = Vec::new();
+    for (index, (page, envelopes)) in pages.into_iter().enumerate() {
+        if page != index {
+            bail!("page {index} is missing");
+        }
+        if index < last && envelopes.len() != page_size {
+            bail!(
+                "page {page} came back short ({} envelopes)",
+                envelopes.len()
+            );
+        }
+        for envelope in envelopes {
+            if !seen.insert(envelope.id.clone()) {
+                bail!("pages overlap on id `{}`", envelope.id);
+            }
+            msgs.push(envelope);
+        }
+    }
+
+    if msgs.len() != exists as usize {
+        bail!("{} envelopes listed, {exists} expected", msgs.len());
+    }
+    Ok(msgs)
+}
+
 /// Routes the envelope diff to the matching backend: snapshot-driven
 /// for m2dir, protocol checkpoint for IMAP / JMAP.
 fn resolve_diff(
@@ -149,14 +473,38 @@ fn resolve_diff(
     side: Side,
     mailbox: &str,
     snapshot: &CacheSnapshot,
-) -> Result<EnvelopeDiff> {
+) -> Result<(EnvelopeDiff, StatTokens)> {
+    let cached = snapshot.state(side, mailbox);
     #[cfg(feature = "m2dir")]
     if client.as_m2dir().is_some() {
         let prev = snapshot.messages(side, mailbox);
-        return crate::sync::diff::diff_envelopes(client, mailbox, prev);
+        return crate::sync::diff::diff_envelopes(client, mailbox, prev, cached);
     }
-    let cached = snapshot.state(side, mailbox);
-    client.diff_envelopes(mailbox, cached).map_err(Into::into)
+    let diff = client.diff_envelopes(mailbox, cached)?;
+    Ok((diff, StatTokens::new()))
+}
+
+/// Requests an IMAP-only step (write guard, checkpoint) costs on
+/// `client`; other backends skip it without a round trip.
+#[cfg_attr(not(feature = "imap"), allow(unused_variables))]
+fn imap_requests(client: &EmailClientStd) -> u64 {
+    #[cfg(feature = "imap")]
+    if client.as_imap().is_some() {
+        return 1;
+    }
+    0
+}
+
+/// SELECTs `mailbox` once before its listing for the UIDVALIDITY the
+/// listing is taken under. Other backends skip it without a round
+/// trip.
+#[cfg_attr(not(feature = "imap"), allow(unused_variables))]
+fn imap_checkpoint(client: &mut EmailClientStd, mailbox: &str) -> Result<Option<u32>> {
+    #[cfg(feature = "imap")]
+    if let Some(selected) = client::imap_select_status(client, mailbox)? {
+        return Ok(selected.uid_validity);
+    }
+    Ok(None)
 }
 
 /// Folds a successful hunk apply into the pre-apply snapshot baseline.
@@ -173,8 +521,8 @@ fn update_snapshot_from_hunk(
             content_key,
             ..
         } => {
-            // NOTE: Copy should always surface the new id on success;
-            // skip silently if absent rather than panic.
+            // NOTE: unverified copies (no id returned nor found)
+            // stay untracked.
             let Some(id) = target_id else {
                 return;
             };
@@ -184,6 +532,7 @@ fn update_snapshot_from_hunk(
                 MessageEntry {
                     id,
                     flags: flags.clone(),
+                    token: None,
                 },
             );
         }
@@ -220,20 +569,1013 @@ fn update_snapshot_from_hunk(
     }
 }
 
+/// Most mailboxes synced at once; each lane needs its own connection
+/// pairs, so more lanes means fewer workers per mailbox.
+const MAX_MAILBOX_LANES: usize = 4;
+
+/// Envelopes per page of a chunked first-sync listing.
+const LIST_PAGE_SIZE: usize = 1000;
+
+/// Lane → main thread messages of stage 3.
+enum LaneEvent {
+    Progress {
+        mailbox: String,
+        progress: MailboxProgress,
+    },
+    Done(Result<MailboxSync>),
+}
+
+/// Progress of one mailbox sync.
+enum MailboxProgress {
+    /// Envelopes listed so far on `side` (chunked listings only).
+    Listed { side: Side, envelopes: usize },
+    /// Hunks applied so far, with the outcome of the last one.
+    Applied {
+        applied: usize,
+        total: usize,
+        hunk: Option<HunkProgress>,
+    },
+}
+
+impl fmt::Display for MailboxProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Listed { side, envelopes } => write!(f, "{envelopes} envelopes listed on {side}"),
+            Self::Applied { applied, total, .. } => {
+                let percent = (applied * 100) / total.max(&1);
+                write!(f, "{percent}%")
+            }
+        }
+    }
+}
+
+/// Read-only inputs every lane needs to sync a mailbox.
+struct MailboxContext<'a> {
+    left_mailboxes: &'a HashSet<String>,
+    right_mailboxes: &'a HashSet<String>,
+    snapshot: &'a CacheSnapshot,
+    left_perms: SidePermissions,
+    right_perms: SidePermissions,
+    dry_run: bool,
+    /// Hunks are only planned here, and applied by [`apply_mailboxes`]
+    /// once reviewed or revalidated against a plan.
+    deferred: bool,
+}
+
+/// Looks copies up on their target when the APPEND left their id
+/// unknown: no UIDPLUS (empty id), or a response lost to a dropped
+/// connection after the message may have landed. The messages added
+/// to the target mailbox since its pre-copy listing are listed, once
+/// per side, and each copy matched to the one new message sharing its
+/// content key, size and sent date. Found copies get their id back;
+/// the others, ambiguous ones included, are recorded unverified by
+/// the fold.
+fn reconcile_copies(
+    lane: &mut Pool,
+    mailbox: &str,
+    left: &SideListing,
+    right: &SideListing,
+    outcomes: &mut [HunkOutcome],
+) {
+    let lost = |outcome: &HunkOutcome| match (&outcome.hunk, &outcome.result) {
+        (EmailHunk::Copy { .. }, Ok(Some(id))) => id.is_empty(),
+        (EmailHunk::Copy { .. }, Ok(None)) => true,
+        (EmailHunk::Copy { .. }, Err(err)) => retry::is_disconnect(err),
+        _ => false,
+    };
+    if !outcomes.iter().any(lost) {
+        return;
+    }
+
+    let by_key = |listing: &SideListing| -> HashMap<u64, Envelope> {
+        listing.pairs.iter().cloned().collect()
+    };
+    let (left_by_key, right_by_key) = (by_key(left), by_key(right));
+
+    for side in [Side::Left, Side::Right] {
+        let targets = |outcome: &HunkOutcome| outcome.hunk.copy_sides().1.contains(&side);
+        let lost_here = |outcome: &HunkOutcome| lost(outcome) && targets(outcome);
+        if !outcomes.iter().any(lost_here) {
+            continue;
+        }
+
+        let (clients, listing) = match side {
+            Side::Left => (&mut lane.left, left),
+            Side::Right => (&mut lane.right, right),
+        };
+        // NOTE: ids already known on this side, listed or just
+        // returned by an APPEND, are never claimed by a match.
+        let mut claimed: HashSet<String> = listing
+            .pairs
+            .iter()
+            .map(|(_, envelope)| envelope.id.clone())
+            .chain(
+                outcomes
+                    .iter()
+                    .filter(|o| matches!(o.hunk, EmailHunk::Copy { .. }) && targets(o))
+                    .filter_map(|o| o.result.as_ref().ok()?.clone()),
+            )
+            .collect();
+
+        let Some(target) = clients.first_mut() else {
+            continue;
+        };
+        lane.requests.add(side, 1);
+        let listed = lane
+            .throttles
+            .side(side)
+            .run(|| list_added_envelopes(target, mailbox, listing.state.as_deref()));
+        let listed = match listed {
+            Ok(listed) => listed,
+            Err(err) => {
+                warn!("{side} listing `{mailbox}` to find copies failed: {err:#}");
+                continue;
+            }
+        };
+
+        for outcome in outcomes.iter_mut().filter(|o| lost_here(o)) {
+            let EmailHunk::Copy {
+                source_side,
+                content_key,
+                ..
+            } = &outcome.hunk
+            else {
+                continue;
+            };
+            let source = match source_side {
+                Side::Left => &left_by_key,
+                Side::Right => &right_by_key,
+            };
+            let Some(envelope) = source.get(content_key) else {
+                continue;
+            };
+
+            match find_copy(&listed, &claimed, *content_key, envelope) {
+                Ok(id) => {
+                    debug!(
+                        "{side} `{mailbox}`: copy of `{}` found as `{id}`",
+                        envelope.id
+                    );
+                    claimed.insert(id.clone());
+                    outcome.result = Ok(Some(id));
+                }
+                Err(matches) => {
+                    if matches > 1 {
+                        warn!(
+                            "{side} `{mailbox}`: {matches} messages match the copy of `{}`, leaving it unverified",
+                            envelope.id
+                        );
+                    }
+                    if outcome.result.is_ok() {
+                        outcome.result = Ok(None);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Envelopes added to `mailbox` since `state`, the listing taken
+/// before the copies; every envelope when there is no such state or
+/// the backend cannot diff from it.
+fn list_added_envelopes(
+    client: &mut EmailClientStd,
+    mailbox: &str,
+    state: Option<&[u8]>,
+) -> Result<Vec<Envelope>> {
+    // NOTE: m2dir states are sidecar tokens, not an envelope-diff
+    // state the backend can diff from.
+    #[cfg(feature = "m2dir")]
+    let state = state.filter(|_| client.as_m2dir().is_none());
+    if let Some(state) = state {
+        match client.diff_envelopes(mailbox, Some(state)) {
+            Ok(EnvelopeDiff::Incremental { new_envelopes, .. }) => return Ok(new_envelopes),
+            Ok(EnvelopeDiff::FullListRequired { .. })
+            | Err(EmailClientStdError::UnsupportedOperation) => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(client.list_envelopes(mailbox, None, None, false)?)
+}
+
+/// Id of the copy of `envelope` among the `listed` target envelopes:
+/// the one unclaimed message sharing its content key, size and sent
+/// date. Otherwise the number of matches (none or several).
+fn find_copy(
+    listed: &[Envelope],
+    claimed: &HashSet<String>,
+    content_key: u64,
+    envelope: &Envelope,
+) -> Result<String, usize> {
+    let matches: Vec<&Envelope> = listed
+        .iter()
+        .filter(|candidate| {
+            !claimed.contains(&candidate.id)
+                && message_key(candidate) == content_key
+                && candidate.size == envelope.size
+                && candidate.date == envelope.date
+        })
+        .collect();
+    match matches.as_slice() {
+        [found] => Ok(found.id.clone()),
+        matches => Err(matches.len()),
+    }
+}
+
+/// Stage-3 result of one mailbox, folded into the snapshot and report
+/// by the main thread once every lane is done.
+struct MailboxSync {
+    mailbox: String,
+    left: SideListing,
+    right: SideListing,
+    collisions: Vec<MessageCollision>,
+    identity_resets: Vec<IdentityReset>,
+    /// Cached baselines re-paired after an identity reset, persisted
+    /// in place of the stale ones.
+    rebased: Vec<(Side, MessageSnapshots)>,
+    /// Hunks side permissions forbid.
+    blocked: Vec<Blocked<EmailHunk>>,
+    /// Hunks not applied yet: never in dry runs, after review in
+    /// interactive ones.
+    planned: Vec<EmailHunk>,
+    /// Hunks turned down by the review, or missing from the plan.
+    rejected: Vec<EmailHunk>,
+    /// Planned hunks the fresh diff no longer yields.
+    stale: Vec<PatchEntry<EmailHunk>>,
+    outcomes: Vec<HunkOutcome>,
+    timings: MailboxTimings,
+}
+
+impl MailboxSync {
+    fn hunk_count(&self) -> usize {
+        self.planned.len() + self.outcomes.len()
+    }
+}
+
+/// Lists, diffs and applies one mailbox on `lane`'s connections.
+fn sync_mailbox(
+    lane: &mut Pool,
+    ctx: &MailboxContext<'_>,
+    mailbox: &str,
+    progress: &(dyn Fn(MailboxProgress) + Sync),
+) -> Result<MailboxSync> {
+    debug!("resolving `{mailbox}` on both sides");
+
+    let left_present = ctx.left_mailboxes.contains(mailbox);
+    let right_present = ctx.right_mailboxes.contains(mailbox);
+    let snapshot = ctx.snapshot;
+
+    lane.first_mut()?;
+    let listed_at = Instant::now();
+    let (left_fetch, right_fetch) = thread::scope(|scope| -> Result<_> {
+        let (left_clients, right_clients) = (&mut lane.left, &mut lane.right);
+        let (requests, throttles) = (&*lane.requests, &*lane.throttles);
+
+        let lh = scope.spawn(move || -> Result<SideListing> {
+            if left_present {
+                let side = Side::Left;
+                fetch_side_envelopes(
+                    left_clients,
+                    side,
+                    mailbox,
+                    snapshot,
+                    requests,
+                    throttles.side(side),
+                    progress,
+                )
+            } else {
+                Ok(SideListing::default())
+            }
+        });
+        let rh = scope.spawn(move || -> Result<SideListing> {
+            if right_present {
+                let side = Side::Right;
+                fetch_side_envelopes(
+                    right_clients,
+                    side,
+                    mailbox,
+                    snapshot,
+                    requests,
+                    throttles.side(side),
+                    progress,
+                )
+            } else {
+                Ok(SideListing::default())
+            }
+        });
+        let left = lh
+            .join()
+            .map_err(|_| anyhow!("Left envelope fetch panicked"))?;
+        let right = rh
+            .join()
+            .map_err(|_| anyhow!("Right envelope fetch panicked"))?;
+        Ok((left, right))
+    })?;
+
+    let left = left_fetch?;
+    let right = right_fetch?;
+    let list_ms = elapsed_ms(listed_at);
+
+    let diffed_at = Instant::now();
+    let mut prev_left = snapshot
+        .messages(Side::Left, mailbox)
+        .cloned()
+        .unwrap_or_default();
+    let mut prev_right = snapshot
+        .messages(Side::Right, mailbox)
+        .cloned()
+        .unwrap_or_default();
+
+    // NOTE: stale cached ids are re-paired by content before diffing,
+    // so a reset shows up as the same messages, not as deletes and
+    // copies. Without a cached UIDVALIDITY (JMAP, m2dir, first IMAP
+    // run) a reset is spotted as every shared message under a new id.
+    let mut identity_resets = Vec::new();
+    let mut rebased = Vec::new();
+    for (side, listing, prev) in [
+        (Side::Left, &left, &mut prev_left),
+        (Side::Right, &right, &mut prev_right),
+    ] {
+        let known = snapshot.uid_validity(side, mailbox).is_some();
+        let reset = listing.reset || (!known && detect_identity_reset(prev, &listing.pairs));
+        if !reset || prev.is_empty() {
+            continue;
+        }
+        let rebound = repair_ids(prev, &listing.pairs);
+        warn!("{side} `{mailbox}` renewed every message id, re-paired {rebound} by content");
+        identity_resets.push(IdentityReset {
+            side,
+            mailbox: mailbox.to_owned(),
+            rebound,
+        });
+        rebased.push((side, prev.clone()));
+    }
+
+    let mut collisions = Vec::new();
+    let left_map = message_map(Side::Left, mailbox, &left.pairs, &mut collisions);
+    let right_map = message_map(Side::Right, mailbox, &right.pairs, &mut collisions);
+
+    let mut blocked = Vec::new();
+    let hunks = diff_messages(
+        mailbox,
+        &left_map,
+        &right_map,
+        &prev_left,
+        &prev_right,
+        ctx.left_perms,
+        ctx.right_perms,
+        &mut blocked,
+    );
+    let timings = MailboxTimings {
+        list_ms,
+        diff_ms: elapsed_ms(diffed_at),
+        apply_ms: 0,
+    };
+
+    let mut sync = MailboxSync {
+        mailbox: mailbox.to_owned(),
+        left,
+        right,
+        collisions,
+        identity_resets,
+        rebased,
+        blocked,
+        planned: hunks,
+        rejected: Vec::new(),
+        stale: Vec::new(),
+        outcomes: Vec::new(),
+        timings,
+    };
+
+    if !ctx.dry_run && !ctx.deferred {
+        apply_mailbox(lane, ctx, &mut sync, progress)?;
+    }
+
+    Ok(sync)
+}
+
+/// Applies the planned hunks of `sync` on `lane`'s connections.
+#[cfg_attr(not(feature = "imap"), allow(unused_variables))]
+fn apply_mailbox(
+    lane: &mut Pool,
+    ctx: &MailboxContext<'_>,
+    sync: &mut MailboxSync,
+    progress: &(dyn Fn(MailboxProgress) + Sync),
+) -> Result<()> {
+    let hunks = mem::take(&mut sync.planned);
+    if hunks.is_empty() {
+        return Ok(());
+    }
+
+    let mailbox = sync.mailbox.as_str();
+    debug!("applying {} hunks in `{mailbox}`", hunks.len());
+    let applied_at = Instant::now();
+
+    // NOTE: pre-select on every lane client in parallel so per-op
+    // IMAP wrappers (running with `auto_select=false`) skip their own
+    // SELECT; lanes own their connections, so no other mailbox can
+    // move them off this one meanwhile.
+    #[cfg(feature = "imap")]
+    thread::scope(|scope| -> Result<()> {
+        let mut handles = Vec::new();
+        if ctx.left_mailboxes.contains(mailbox) {
+            let selects = lane.left.iter().map(imap_requests).sum();
+            lane.requests.add(Side::Left, selects);
+            for c in lane.left.iter_mut() {
+                handles.push(scope.spawn(move || client::imap_select(c, mailbox)));
+            }
+        }
+        if ctx.right_mailboxes.contains(mailbox) {
+            let selects = lane.right.iter().map(imap_requests).sum();
+            lane.requests.add(Side::Right, selects);
+            for c in lane.right.iter_mut() {
+                handles.push(scope.spawn(move || client::imap_select(c, mailbox)));
+            }
+        }
+        for h in handles {
+            h.join()
+                .map_err(|_| anyhow!("IMAP pre-select worker panicked"))??;
+        }
+        Ok(())
+    })?;
+
+    let guards = WriteGuards {
+        left: sync
+            .left
+            .guard
+            .clone()
+            .map(|guard| Arc::new(Mutex::new(guard))),
+        right: sync
+            .right
+            .guard
+            .clone()
+            .map(|guard| Arc::new(Mutex::new(guard))),
+    };
+    sync.outcomes = lane.apply_in_mailbox(mailbox, hunks, &guards, |applied, total, outcome| {
+        let hunk = outcome.map(|outcome| {
+            let (hunk, result) = (&outcome.hunk, &outcome.result);
+            hunk_progress(mailbox, hunk, result, applied, total)
+        });
+        progress(MailboxProgress::Applied {
+            applied,
+            total,
+            hunk,
+        })
+    })?;
+
+    reconcile_copies(lane, mailbox, &sync.left, &sync.right, &mut sync.outcomes);
+
+    sync.timings.apply_ms = elapsed_ms(applied_at);
+    Ok(())
+}
+
+/// Stage 3: syncs `mailboxes` over up to [`MAX_MAILBOX_LANES`] lanes
+/// of `pool`, listing, diffing and applying several mailboxes at once;
+/// results come back sorted by mailbox. The first mailbox error stops
+/// the lanes from picking new mailboxes and is returned.
+fn sync_mailboxes(
+    pool: &mut Pool,
+    ctx: &MailboxContext<'_>,
+    observer: &mut dyn SyncObserver,
+    mailboxes: &BTreeSet<String>,
+) -> Result<Vec<MailboxSync>> {
+    let total_mailboxes = mailboxes.len();
+    let started_at = Instant::now();
+    let message = format!("[0/{total_mailboxes}] Syncing mailboxes…");
+    observer.stage_started(Stage::Sync, &message);
+
+    let jobs = mailboxes
+        .iter()
+        .map(|mailbox| (mailbox.clone(), ()))
+        .collect();
+    let synced = run_lanes(
+        pool,
+        jobs,
+        observer,
+        Stage::Sync,
+        |lane, mailbox, (), progress| sync_mailbox(lane, ctx, mailbox, progress),
+    )?;
+
+    let message_hunks: usize = synced.iter().map(MailboxSync::hunk_count).sum();
+    let message = if ctx.deferred {
+        format!("Diffed {total_mailboxes} mailboxes: {message_hunks} message hunks to review")
+    } else {
+        format!(
+            "Synced {total_mailboxes} mailboxes: {message_hunks} message hunks{}",
+            if ctx.dry_run { " (dry-run)" } else { "" }
+        )
+    };
+    observer.stage_finished(Stage::Sync, &message, elapsed_ms(started_at));
+
+    Ok(synced)
+}
+
+/// Applies the reviewed hunks of mailboxes planned by
+/// [`sync_mailboxes`], over lanes the same way.
+fn apply_mailboxes(
+    pool: &mut Pool,
+    ctx: &MailboxContext<'_>,
+    observer: &mut dyn SyncObserver,
+    synced: Vec<MailboxSync>,
+) -> Result<Vec<MailboxSync>> {
+    let total_mailboxes = synced.len();
+    let started_at = Instant::now();
+    let message = format!("[0/{total_mailboxes}] Applying mailboxes…");
+    observer.stage_started(Stage::Apply, &message);
+
+    let jobs = synced
+        .into_iter()
+        .map(|sync| (sync.mailbox.clone(), sync))
+        .collect();
+    let applied = run_lanes(
+        pool,
+        jobs,
+        observer,
+        Stage::Apply,
+        |lane, _, mut sync, progress| {
+            apply_mailbox(lane, ctx, &mut sync, progress)?;
+            Ok(sync)
+        },
+    )?;
+
+    let message_hunks: usize = applied.iter().map(MailboxSync::hunk_count).sum();
+    let message = format!("Applied {message_hunks} message hunks in {total_mailboxes} mailboxes");
+    observer.stage_finished(Stage::Apply, &message, elapsed_ms(started_at));
+
+    Ok(applied)
+}
+
+/// Observer view of the `applied`th hunk outcome out of `total`.
+fn hunk_progress<T>(
+    mailbox: &str,
+    hunk: &impl fmt::Display,
+    result: &Result<T>,
+    applied: usize,
+    total: usize,
+) -> HunkProgress {
+    let (status, error) = match result {
+        Ok(_) => (HunkStatus::Applied, None),
+        Err(err) if err.is::<ModifiedConcurrently>() => (HunkStatus::Skipped, None),
+        Err(err) => (HunkStatus::Failed, Some(format!("{err:#}"))),
+    };
+    HunkProgress {
+        mailbox: mailbox.to_owned(),
+        hunk: hunk.to_string(),
+        status,
+        error,
+        applied,
+        total,
+    }
+}
+
+/// Runs `job` once per `(mailbox, input)` over up to
+/// [`MAX_MAILBOX_LANES`] lanes of `pool`, reporting the progress of
+/// `stage` to `observer` from the calling thread.
+fn run_lanes<T, F>(
+    pool: &mut Pool,
+    jobs: Vec<(String, T)>,
+    observer: &mut dyn SyncObserver,
+    stage: Stage,
+    job: F,
+) -> Result<Vec<MailboxSync>>
+where
+    T: Send,
+    F: Fn(&mut Pool, &str, T, &(dyn Fn(MailboxProgress) + Sync)) -> Result<MailboxSync> + Sync,
+{
+    let total_mailboxes = jobs.len();
+    let verb = match stage {
+        Stage::Apply => "Applying",
+        _ => "Syncing",
+    };
+    let lane_count = pool
+        .worker_count()
+        .min(MAX_MAILBOX_LANES)
+        .min(total_mailboxes)
+        .max(1);
+    debug!("{verb} {total_mailboxes} mailboxes over {lane_count} lanes");
+
+    let mut lanes = pool.split_lanes(lane_count)?;
+
+    let queue: SegQueue<(String, T)> = SegQueue::new();
+    for job in jobs {
+        queue.push(job);
+    }
+    let cancelled = AtomicBool::new(false);
+    let (event_tx, event_rx) = mpsc::channel::<LaneEvent>();
+
+    let synced = thread::scope(|scope| -> Result<Vec<Result<MailboxSync>>> {
+        let mut handles = Vec::with_capacity(lanes.len());
+        for lane in lanes.iter_mut() {
+            let tx = event_tx.clone();
+            let (queue, cancelled, job) = (&queue, &cancelled, &job);
+
+            handles.push(scope.spawn(move || {
+                while !cancelled.load(Ordering::Relaxed) {
+                    let Some((mailbox, input)) = queue.pop() else {
+                        break;
+                    };
+                    let progress = |progress| {
+                        let event = LaneEvent::Progress {
+                            mailbox: mailbox.clone(),
+                            progress,
+                        };
+                        let _ = tx.send(event);
+                    };
+                    let result = job(lane, &mailbox, input, &progress);
+                    if result.is_err() {
+                        cancelled.store(true, Ordering::Relaxed);
+                    }
+                    if tx.send(LaneEvent::Done(result)).is_err() {
+                        break;
+                    }
+                }
+            }));
+        }
+        drop(event_tx);
+
+        let mut synced = Vec::with_capacity(total_mailboxes);
+        while let Ok(event) = event_rx.recv() {
+            match event {
+                LaneEvent::Progress { mailbox, progress } => {
+                    if let MailboxProgress::Applied {
+                        hunk: Some(hunk), ..
+                    } = &progress
+                    {
+                        observer.hunk_finished(hunk);
+                    }
+                    let done = synced.len();
+                    let message =
+                        format!("[{done}/{total_mailboxes}] {verb} {mailbox} ({progress})");
+                    observer.stage_progress(stage, &message);
+                }
+                LaneEvent::Done(result) => {
+                    let mailbox = result.as_ref().ok().map(|sync| sync.mailbox.clone());
+                    if let Ok(sync) = &result {
+                        debug!("{}: {} message hunks", sync.mailbox, sync.hunk_count());
+                    }
+                    synced.push(result);
+                    let done = synced.len();
+                    if let Some(mailbox) = mailbox {
+                        observer.mailbox_finished(stage, &mailbox, done, total_mailboxes);
+                    }
+                    let message = format!("[{done}/{total_mailboxes}] {verb} mailboxes…");
+                    observer.stage_progress(stage, &message);
+                }
+            }
+        }
+
+        for handle in handles {
+            handle
+                .join()
+                .map_err(|_| anyhow!("Mailbox lane panicked"))?;
+        }
+        Ok(synced)
+    })?;
+
+    pool.merge_lanes(lanes);
+
+    let mut synced = synced.into_iter().collect::<Result<Vec<_>>>()?;
+    synced.sort_by(|a, b| a.mailbox.cmp(&b.mailbox));
+    Ok(synced)
+}
+
+/// Puts the planned message hunks of every mailbox up for review at
+/// once, moving the rejected ones aside.
+fn review_mailboxes(observer: &mut dyn SyncObserver, synced: &mut [MailboxSync]) -> Result<()> {
+    let hunks: Vec<EmailHunk> = synced
+        .iter_mut()
+        .flat_map(|sync| mem::take(&mut sync.planned))
+        .collect();
+    let (approved, rejected) = review::review_hunks(observer, "Message patch", hunks)?;
+
+    let index: HashMap<String, usize> = synced
+        .iter()
+        .enumerate()
+        .map(|(i, sync)| (sync.mailbox.clone(), i))
+        .collect();
+    for (hunk, approved) in approved
+        .into_iter()
+        .map(|hunk| (hunk, true))
+        .chain(rejected.into_iter().map(|hunk| (hunk, false)))
+    {
+        let Some(&i) = index.get(hunk.mailbox()) else {
+            bail!("Reviewed hunk for unknown mailbox `{}`", hunk.mailbox());
+        };
+        if approved {
+            synced[i].planned.push(hunk);
+        } else {
+            synced[i].rejected.push(hunk);
+        }
+    }
+
+    Ok(())
+}
+
+/// Keeps the planned message hunks of `plan` the fresh diff of
+/// `synced` still yields; fresh hunks missing from the plan are left
+/// out like rejected ones, planned hunks no longer yielded are
+/// reported stale. Fails when a side of a mailbox moved on since the
+/// plan, before any message hunk is applied.
+fn revalidate_mailboxes(
+    plan: &Plan,
+    snapshot: &CacheSnapshot,
+    synced: &mut [MailboxSync],
+    report: &mut SyncReport,
+) -> Result<()> {
+    for sync in synced.iter_mut() {
+        let hunks = mem::take(&mut sync.planned);
+        let Some(planned) = plan.mailbox_plan(&sync.mailbox) else {
+            sync.rejected = hunks;
+            continue;
+        };
+
+        for (side, listing) in [(Side::Left, &sync.left), (Side::Right, &sync.right)] {
+            let baseline = snapshot.messages(side, &sync.mailbox);
+            let checkpoint = Checkpoint::new(&listing.pairs, baseline);
+            let expected = planned.checkpoint(side);
+            if checkpoint != *expected {
+                bail!(
+                    "Plan is stale: `{}` changed on {side} since it was made (listing {} → {}, baseline {} → {}), plan again",
+                    sync.mailbox,
+                    expected.listing,
+                    checkpoint.listing,
+                    expected.baseline,
+                    checkpoint.baseline,
+                );
+            }
+        }
+
+        let (valid, unplanned, stale) = plan::revalidate(&planned.hunks, hunks);
+        sync.planned = valid;
+        sync.rejected = unplanned;
+        sync.stale = stale
+            .into_iter()
+            .map(|hunk| PatchEntry::new(hunk, None).skipped(plan::STALE))
+            .collect();
+    }
+
+    for planned in &plan.mailboxes {
+        if synced.iter().any(|sync| sync.mailbox == planned.mailbox) {
+            continue;
+        }
+        for hunk in &planned.hunks {
+            let entry = PatchEntry::new(hunk.clone(), None);
+            let reason = format!("stale: `{}` is no longer synced", planned.mailbox);
+            report.email.patch.push(entry.skipped(reason));
+        }
+    }
+
+    Ok(())
+}
+
+/// Inputs a mailbox's message hunks were diffed from, kept to explain
+/// them once the snapshot has moved on.
+struct Explainer<'a> {
+    left: MessageMap<'a>,
+    right: MessageMap<'a>,
+    prev_left: MessageSnapshots,
+    prev_right: MessageSnapshots,
+}
+
+impl<'a> Explainer<'a> {
+    fn new(
+        snapshot: &CacheSnapshot,
+        mailbox: &str,
+        left: &'a EnvelopePairs,
+        right: &'a EnvelopePairs,
+    ) -> Self {
+        // NOTE: first envelope wins on collisions, as in `message_map`.
+        let index = |pairs: &'a EnvelopePairs| {
+            let mut map = MessageMap::with_capacity(pairs.len());
+            for (key, envelope) in pairs {
+                map.entry(*key).or_insert(envelope);
+            }
+            map
+        };
+        let prev = |side| {
+            snapshot
+                .messages(side, mailbox)
+                .cloned()
+                .unwrap_or_default()
+        };
+        Self {
+            left: index(left),
+            right: index(right),
+            prev_left: prev(Side::Left),
+            prev_right: prev(Side::Right),
+        }
+    }
+
+    /// Attaches the envelope of the message `entry` is about (the
+    /// source one for copies) and the reason the diff emitted it.
+    fn explain(&self, entry: PatchEntry<EmailHunk>) -> PatchEntry<EmailHunk> {
+        let key = entry.hunk.content_key();
+        let left = self.left.get(&key).copied();
+        let right = self.right.get(&key).copied();
+        let key = key.to_string();
+        let reason = explain_message(
+            &entry.hunk,
+            left,
+            right,
+            self.prev_left.get(&key),
+            self.prev_right.get(&key),
+        );
+        let side = match &entry.hunk {
+            EmailHunk::Copy { source_side, .. } => *source_side,
+            EmailHunk::AddFlags { side, .. }
+            | EmailHunk::RemoveFlags { side, .. }
+            | EmailHunk::Delete { side, .. } => *side,
+        };
+        // NOTE: envelopes rebuilt from a delta only carry an id and
+        // flags, they have no details worth showing.
+        let envelope = match side {
+            Side::Left => left,
+            Side::Right => right,
+        }
+        .filter(|env| env.size > 0 || env.date.is_some() || !env.from.is_empty());
+        entry.explained(envelope.map(MessageDetails::from), reason)
+    }
+}
+
+/// Folds one mailbox's stage-3 result into the snapshot (pre-apply
+/// baseline plus every successful hunk) and the report.
+fn fold_mailbox_sync(
+    snapshot: &mut CacheSnapshot,
+    report: &mut SyncReport,
+    sync: MailboxSync,
+    dry_run: bool,
+) {
+    let MailboxSync {
+        mailbox,
+        left,
+        right,
+        collisions,
+        identity_resets,
+        rebased,
+        blocked,
+        planned,
+        rejected,
+        stale,
+        outcomes,
+        timings,
+    } = sync;
+
+    report.collisions.extend(collisions);
+    report.identity_resets.extend(identity_resets);
+    report.blocked.email.extend(blocked);
+    for (side, entries) in rebased {
+        snapshot.set_messages(side, mailbox.clone(), entries);
+    }
+
+    let explainer = Explainer::new(snapshot, &mailbox, &left.pairs, &right.pairs);
+    let first = report.email.patch.len();
+
+    if dry_run {
+        for hunk in planned {
+            let entry = explainer.explain(PatchEntry::new(hunk, None));
+            report.email.patch.push(entry);
+        }
+        record_mailbox_stats(report, mailbox, first, timings);
+        return;
+    }
+
+    // NOTE: messages refused as modified concurrently keep their
+    // previous baseline on both sides, so the next run sees both the
+    // change behind the hunk and the concurrent one, and merges them.
+    // Rejected hunks keep it too, so the next run proposes them again.
+    let refused = outcomes.iter().filter_map(|outcome| match &outcome.result {
+        Err(err) if err.is::<ModifiedConcurrently>() => Some(&outcome.hunk),
+        _ => None,
+    });
+    let mut kept = Vec::new();
+    for hunk in refused.chain(&rejected) {
+        let key = hunk.content_key().to_string();
+        for side in [Side::Left, Side::Right] {
+            let prev = snapshot
+                .messages(side, &mailbox)
+                .and_then(|entries| entries.get(&key))
+                .cloned();
+            kept.push((side, key.clone(), prev));
+        }
+    }
+
+    for (side, listing) in [(Side::Left, &left), (Side::Right, &right)] {
+        let entries = pairs_to_snapshot(&listing.pairs, &listing.tokens);
+        snapshot.set_messages(side, mailbox.clone(), entries);
+        match &listing.state {
+            Some(state) => snapshot.set_state(side, mailbox.clone(), state.clone()),
+            None if listing.reset => snapshot.clear_state(side, &mailbox),
+            None => {}
+        }
+        if let Some(uid_validity) = listing.uid_validity {
+            snapshot.set_uid_validity(side, mailbox.clone(), uid_validity);
+        }
+    }
+
+    for (side, key, prev) in kept {
+        let entries = snapshot.messages_mut(side, &mailbox);
+        match prev {
+            Some(entry) => entries.insert(key, entry),
+            None => entries.remove(&key),
+        };
+    }
+
+    for outcome in outcomes {
+        let HunkOutcome {
+            hunk,
+            result,
+            retries,
+        } = outcome;
+        let entry = match result {
+            Ok(target_id) => {
+                let target_id = target_id.filter(|id| !id.is_empty());
+                let copied = matches!(hunk, EmailHunk::Copy { .. });
+                update_snapshot_from_hunk(snapshot, &mailbox, &hunk, target_id.clone());
+                let entry = PatchEntry::new(hunk, None);
+                match target_id {
+                    Some(id) if copied => entry.landed(id),
+                    None if copied => entry.unverified(),
+                    _ => entry,
+                }
+            }
+            Err(err) if err.is::<ModifiedConcurrently>() => {
+                PatchEntry::new(hunk, None).skipped(ModifiedConcurrently.to_string())
+            }
+            Err(err) => PatchEntry::new(hunk, Some(err)),
+        };
+        let entry = explainer.explain(entry.with_retries(retries));
+        report.email.patch.push(entry);
+    }
+
+    report.email.patch.extend(stale);
+    report.rejected.email.extend(rejected);
+    record_mailbox_stats(report, mailbox, first, timings);
+}
+
+/// Tallies the entries of `mailbox`, pushed to the report from
+/// `first` on, into its [`MailboxStats`] and the run totals.
+fn record_mailbox_stats(
+    report: &mut SyncReport,
+    mailbox: String,
+    first: usize,
+    timings: MailboxTimings,
+) {
+    let stats = MailboxStats::new(mailbox, &report.email.patch[first..], timings);
+    let totals = &mut report.stats;
+    totals.bytes.add(stats.bytes);
+    totals.timings.list_ms += timings.list_ms;
+    totals.timings.diff_ms += timings.diff_ms;
+    totals.timings.apply_ms += timings.apply_ms;
+    report.mailboxes.push(stats);
+}
+
+/// What [`run`] does with the computed patch.
+pub enum SyncMode<'a> {
+    /// Applies every hunk.
+    Apply,
+    /// Applies nothing; the report lists the patch.
+    DryRun,
+    /// Applies the hunks [`SyncObserver::review`] approves.
+    Review,
+    /// Applies nothing; the patch and the checkpoints it was computed
+    /// against are recorded into the plan.
+    Plan(&'a mut Plan),
+    /// Applies the hunks of a saved plan the fresh diff still yields.
+    ApplyPlan(&'a Plan),
+}
+
+impl SyncMode<'_> {
+    fn dry_run(&self) -> bool {
+        matches!(self, Self::DryRun | Self::Plan(_))
+    }
+
+    fn deferred(&self) -> bool {
+        matches!(self, Self::Review | Self::ApplyPlan(_))
+    }
+}
+
 /// Runs the sync end-to-end and returns a [`SyncReport`] pairing every
 /// applied hunk with its error (if any).
+///
+/// `mailbox_filter` overrides the filters of `account_config`, whose
+/// retry policy replaces the one of `pool`; stages, mailboxes and hunk
+/// outcomes are reported to `observer` as they go.
 pub fn run(
     account_name: impl Into<String>,
     account_config: &AccountConfig,
     mut pool: Pool,
     mailbox_filter: Option<MailboxFilter>,
-    dry_run: bool,
+    mut mode: SyncMode<'_>,
+    observer: &mut dyn SyncObserver,
 ) -> Result<SyncReport> {
+    let started_at = Instant::now();
     let account_name = account_name.into();
+    let dry_run = mode.dry_run();
     let left_perms = account_config.left.permissions();
     let right_perms = account_config.right.permissions();
 
     let mailbox_filter = mailbox_filter.unwrap_or_else(|| account_config.mailbox.filters.clone());
+    pool.retry = account_config.retry.clone();
 
     let mut report = SyncReport {
         account: account_name.clone(),
@@ -241,19 +1583,28 @@ pub fn run(
         ..Default::default()
     };
 
+    hook::pre_sync(&account_name, account_config, dry_run)?;
+
     let cache_path = CacheSnapshot::path(&account_name)?;
     let mut snapshot = CacheSnapshot::load(&cache_path)?;
 
     // 1. list + filter mailboxes (left and right probed in parallel).
-    let s = Spinner::start("Listing mailboxes…");
+    observer.stage_started(Stage::Probe, "Listing mailboxes…");
+    let probed_at = Instant::now();
+    let requests = pool.requests.clone();
+    let throttles = pool.throttles.clone();
 
     let (left_outcome, right_outcome) = thread::scope(|scope| -> Result<_> {
-        let left_client = &mut pool.left[0];
-        let right_client = &mut pool.right[0];
-        let snap = &snapshot;
+        let (left_client, right_client) = pool.first_mut()?;
+        let (snap, requests) = (&snapshot, &*requests);
+        let (left_throttle, right_throttle) = (&throttles.left, &throttles.right);
 
-        let lh = scope.spawn(move || probe_side_mailboxes(left_client, Side::Left, snap));
-        let rh = scope.spawn(move || probe_side_mailboxes(right_client, Side::Right, snap));
+        let lh = scope.spawn(move || {
+            probe_side_mailboxes(left_client, Side::Left, snap, requests, left_throttle)
+        });
+        let rh = scope.spawn(move || {
+            probe_side_mailboxes(right_client, Side::Right, snap, requests, right_throttle)
+        });
         let left = lh
             .join()
             .map_err(|_| anyhow!("Left mailbox probe panicked"))?;
@@ -265,6 +1616,7 @@ pub fn run(
 
     let (left_mailboxes, left_mailbox_state) = left_outcome?;
     let (right_mailboxes, right_mailbox_state) = right_outcome?;
+    report.stats.timings.probe_ms = elapsed_ms(probed_at);
 
     if let Some(state) = left_mailbox_state {
         snapshot.set_mailbox_state(Side::Left, state);
@@ -273,11 +1625,12 @@ pub fn run(
         snapshot.set_mailbox_state(Side::Right, state);
     }
 
-    s.success(format!(
+    let message = format!(
         "Listed mailboxes ({} left, {} right)",
         left_mailboxes.len(),
         right_mailboxes.len()
-    ));
+    );
+    observer.stage_finished(Stage::Probe, &message, report.stats.timings.probe_ms);
 
     let left_filtered = filter_mailboxes(&left_mailboxes, &mailbox_filter);
     let right_filtered = filter_mailboxes(&right_mailboxes, &mailbox_filter);
@@ -285,15 +1638,40 @@ pub fn run(
     // 2. compute + apply mailbox patch (fanned out across worker pairs).
     let prev_left_mailboxes = snapshot.mailbox_names(Side::Left);
     let prev_right_mailboxes = snapshot.mailbox_names(Side::Right);
-    let mailbox_hunks = diff_mailboxes(
+    let mut mailbox_hunks = diff_mailboxes(
         &left_filtered,
         &right_filtered,
         &prev_left_mailboxes,
         &prev_right_mailboxes,
         left_perms,
         right_perms,
+        &mut report.blocked.mailbox,
     );
 
+    // NOTE: rejected mailbox hunks need no snapshot care: the mailbox
+    // stays out of the common set, so its cache is left untouched.
+    match &mut mode {
+        SyncMode::Review => {
+            let (approved, rejected) =
+                review::review_hunks(observer, "Mailbox patch", mailbox_hunks)?;
+            mailbox_hunks = approved;
+            report.rejected.mailbox = rejected;
+        }
+        SyncMode::Plan(plan) => {
+            plan.mailbox = mailbox_hunks.clone();
+        }
+        SyncMode::ApplyPlan(plan) => {
+            let (valid, unplanned, stale) = plan::revalidate(&plan.mailbox, mailbox_hunks);
+            mailbox_hunks = valid;
+            report.rejected.mailbox = unplanned;
+            for hunk in stale {
+                let entry = PatchEntry::new(hunk, None).skipped(plan::STALE);
+                report.mailbox.patch.push(entry);
+            }
+        }
+        SyncMode::Apply | SyncMode::DryRun => {}
+    }
+
     debug!(
         "mailbox patch: {} hunks{}",
         mailbox_hunks.len(),
@@ -302,24 +1680,43 @@ pub fn run(
 
     let mailbox_hunk_count = mailbox_hunks.len();
     if mailbox_hunk_count > 0 {
-        let s = Spinner::start(format!("Patching {mailbox_hunk_count} mailbox hunks…"));
+        let message = format!("Patching {mailbox_hunk_count} mailbox hunks…");
+        observer.stage_started(Stage::MailboxPatch, &message);
+        let patched_at = Instant::now();
         if dry_run {
             for h in mailbox_hunks {
-                report.mailbox.patch.push(PatchEntry::new(h, None));
-            }
-        } else {
-            let outcomes = pool.apply_mailbox_hunks(mailbox_hunks, |_, _| {})?;
-            for MailboxHunkOutcome { hunk, result } in outcomes {
+                let reason = explain_mailbox(&h);
                 report
                     .mailbox
                     .patch
-                    .push(PatchEntry::new(hunk, result.err()));
+                    .push(PatchEntry::new(h, None).explained(None, reason));
+            }
+        } else {
+            let applied_at = Instant::now();
+            let outcomes = pool.apply_mailbox_hunks(mailbox_hunks, |applied, total, outcome| {
+                if let Some(outcome) = outcome {
+                    let (hunk, result) = (&outcome.hunk, &outcome.result);
+                    let progress = hunk_progress(hunk.mailbox(), hunk, result, applied, total);
+                    observer.hunk_finished(&progress);
+                }
+            })?;
+            report.stats.timings.apply_ms += elapsed_ms(applied_at);
+            for MailboxHunkOutcome {
+                hunk,
+                result,
+                retries,
+            } in outcomes
+            {
+                let reason = explain_mailbox(&hunk);
+                let entry = PatchEntry::new(hunk, result.err()).with_retries(retries);
+                report.mailbox.patch.push(entry.explained(None, reason));
             }
         }
-        s.success(format!(
+        let message = format!(
             "Patched {mailbox_hunk_count} mailbox hunks{}",
             if dry_run { " (dry-run)" } else { "" }
-        ));
+        );
+        observer.stage_finished(Stage::MailboxPatch, &message, elapsed_ms(patched_at));
     }
 
     // 3. message patch, per common mailbox (post-stage-2 set: keep
@@ -329,7 +1726,7 @@ pub fn run(
         .cloned()
         .collect();
     for entry in &report.mailbox.patch {
-        let applied = dry_run || entry.error.is_none();
+        let applied = (dry_run || entry.error.is_none()) && entry.skipped.is_none();
         if !applied {
             continue;
         }
@@ -359,154 +1756,156 @@ pub fn run(
         );
     }
 
-    for (index, mailbox) in common.iter().enumerate() {
-        let position = index + 1;
-        let prefix = format!("[{position}/{total_mailboxes}] Syncing {mailbox}");
-        let s = Spinner::start(format!("{prefix} (0%)"));
-        debug!("resolving `{mailbox}` on both sides");
-
-        let left_present = left_filtered.contains(mailbox);
-        let right_present = right_filtered.contains(mailbox);
-        let mailbox_str = mailbox.as_str();
-
-        let (left_fetch, right_fetch) = thread::scope(|scope| -> Result<_> {
-            let left_client = &mut pool.left[0];
-            let right_client = &mut pool.right[0];
-            let snap = &snapshot;
-
-            let lh = scope.spawn(move || -> Result<(EnvelopePairs, Option<Vec<u8>>)> {
-                if left_present {
-                    fetch_side_envelopes(left_client, Side::Left, mailbox_str, snap)
-                } else {
-                    Ok((Vec::new(), None))
-                }
-            });
-            let rh = scope.spawn(move || -> Result<(EnvelopePairs, Option<Vec<u8>>)> {
-                if right_present {
-                    fetch_side_envelopes(right_client, Side::Right, mailbox_str, snap)
-                } else {
-                    Ok((Vec::new(), None))
-                }
-            });
-            let left = lh
-                .join()
-                .map_err(|_| anyhow!("Left envelope fetch panicked"))?;
-            let right = rh
-                .join()
-                .map_err(|_| anyhow!("Right envelope fetch panicked"))?;
-            Ok((left, right))
-        })?;
-
-        let (left_pairs, left_state) = left_fetch?;
-        let (right_pairs, right_state) = right_fetch?;
-
-        let left_map = message_map(Side::Left, mailbox, &left_pairs, &mut report.collisions);
-        let right_map = message_map(Side::Right, mailbox, &right_pairs, &mut report.collisions);
-
-        let prev_left = snapshot
-            .messages(Side::Left, mailbox)
-            .cloned()
-            .unwrap_or_default();
-        let prev_right = snapshot
-            .messages(Side::Right, mailbox)
-            .cloned()
-            .unwrap_or_default();
-
-        let hunks = diff_messages(
-            mailbox,
-            &left_map,
-            &right_map,
-            &prev_left,
-            &prev_right,
+    if total_mailboxes > 0 {
+        let ctx = MailboxContext {
+            left_mailboxes: &left_filtered,
+            right_mailboxes: &right_filtered,
+            snapshot: &snapshot,
             left_perms,
             right_perms,
-        );
+            dry_run,
+            deferred: mode.deferred(),
+        };
+        let mut synced = sync_mailboxes(&mut pool, &ctx, observer, &common)?;
 
-        // NOTE: capture the pre-apply baseline now; the outcome loop
-        // below folds each successful hunk into it so stage 4 persists
-        // the post-apply state.
-        if !dry_run {
-            snapshot.set_messages(Side::Left, mailbox.clone(), pairs_to_snapshot(&left_pairs));
-            snapshot.set_messages(
-                Side::Right,
-                mailbox.clone(),
-                pairs_to_snapshot(&right_pairs),
-            );
-            if let Some(state) = left_state {
-                snapshot.set_state(Side::Left, mailbox.clone(), state);
+        match &mut mode {
+            SyncMode::Review => {
+                review_mailboxes(observer, &mut synced)?;
+                synced = apply_mailboxes(&mut pool, &ctx, observer, synced)?;
             }
-            if let Some(state) = right_state {
-                snapshot.set_state(Side::Right, mailbox.clone(), state);
+            SyncMode::Plan(plan) => {
+                plan.mailboxes = synced
+                    .iter()
+                    .map(|sync| MailboxPlan {
+                        mailbox: sync.mailbox.clone(),
+                        left: Checkpoint::new(
+                            &sync.left.pairs,
+                            snapshot.messages(Side::Left, &sync.mailbox),
+                        ),
+                        right: Checkpoint::new(
+                            &sync.right.pairs,
+                            snapshot.messages(Side::Right, &sync.mailbox),
+                        ),
+                        hunks: sync.planned.clone(),
+                    })
+                    .collect();
             }
-        }
-
-        let total = hunks.len();
-        if total == 0 {
-            s.clear();
-            continue;
-        }
-
-        debug!("applying {total} hunks in `{mailbox}`");
-
-        if dry_run {
-            for hunk in hunks {
-                report.email.patch.push(PatchEntry::new(hunk, None));
-            }
-        } else {
-            // NOTE: pre-select on every pool client in parallel so per-op
-            // IMAP wrappers (running with `auto_select=false`) skip their
-            // own SELECT.
-            #[cfg(feature = "imap")]
-            thread::scope(|scope| -> Result<()> {
-                let mut handles = Vec::new();
-                if left_present {
-                    for client in pool.left.iter_mut() {
-                        handles.push(scope.spawn(move || imap_select(client, mailbox_str)));
-                    }
-                }
-                if right_present {
-                    for client in pool.right.iter_mut() {
-                        handles.push(scope.spawn(move || imap_select(client, mailbox_str)));
-                    }
-                }
-                for h in handles {
-                    h.join()
-                        .map_err(|_| anyhow!("IMAP pre-select worker panicked"))??;
-                }
-                Ok(())
-            })?;
-
-            let outcomes = pool.apply_in_mailbox(mailbox, hunks, |applied, total| {
-                let percent = (applied * 100) / total.max(1);
-                s.set_message(format!("{prefix} ({percent}%)"));
-            })?;
-            for outcome in outcomes {
-                let HunkOutcome { hunk, result } = outcome;
-                match result {
-                    Ok(target_id) => {
-                        update_snapshot_from_hunk(&mut snapshot, mailbox, &hunk, target_id);
-                        report.email.patch.push(PatchEntry::new(hunk, None));
-                    }
-                    Err(err) => {
-                        report.email.patch.push(PatchEntry::new(hunk, Some(err)));
-                    }
-                }
+            SyncMode::ApplyPlan(plan) => {
+                revalidate_mailboxes(plan, &snapshot, &mut synced, &mut report)?;
+                synced = apply_mailboxes(&mut pool, &ctx, observer, synced)?;
             }
+            SyncMode::Apply | SyncMode::DryRun => {}
         }
 
-        s.success(format!(
-            "{mailbox}: {total} message hunks{}",
-            if dry_run { " (dry-run)" } else { "" }
-        ));
+        // NOTE: lanes only read the snapshot; fold their results here,
+        // in mailbox order, so the report stays deterministic.
+        for sync in synced {
+            fold_mailbox_sync(&mut snapshot, &mut report, sync, dry_run);
+        }
     }
 
+    report.throttle = pool.throttles.report();
+    report.stats.requests = pool.requests.report();
+
     // 4. persist post-sync snapshot.
     if !dry_run {
-        let s = Spinner::start("Persisting snapshot…");
+        observer.stage_started(Stage::Persist, "Persisting snapshot…");
+        let persisted_at = Instant::now();
         debug!("persisting snapshot at `{}`", cache_path.display());
         snapshot.record(&report.mailbox.patch, &cache_path)?;
-        s.success("Persisted snapshot");
+        report.stats.timings.persist_ms = elapsed_ms(persisted_at);
+        let elapsed_ms = report.stats.timings.persist_ms;
+        observer.stage_finished(Stage::Persist, "Persisted snapshot", elapsed_ms);
+    }
+
+    report.stats.timings.total_ms = elapsed_ms(started_at);
+
+    // 5. run the post-sync hooks, now that the snapshot is safe.
+    hook::post_sync(account_config, &mut report);
+
+    // 6. record the run in the account history (dry runs included).
+    if let Err(err) = history::path(&account_name).and_then(|p| history::append(&p, &report)) {
+        warn!("cannot record sync history: {err:#}");
     }
 
     Ok(report)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use io_email::envelope::Envelope;
+
+    use super::*;
+
+    fn envelope(id: &str, message_id: &str, size: u64) -> Envelope {
+        Envelope {
+            id: id.to_string(),
+            message_id: Some(message_id.to_string()),
+            flags: BTreeSet::new(),
+            subject: String::new(),
+            from: Vec::new(),
+            to: Vec::new(),
+            date: None,
+            size,
+            has_attachment: None,
+        }
+    }
+
+    fn page(page: usize, ids: std::ops::Range<usize>) -> (usize, Vec<Envelope>) {
+        let envelopes = ids
+            .map(|id| envelope(&id.to_string(), "a@example.org", 1))
+            .collect();
+        (page, envelopes)
+    }
+
+    #[test]
+    fn zero_based_pages_are_merged_in_order() {
+        let pages = vec![page(2, 4..5), page(0, 0..2), page(3, 5..5), page(1, 2..4)];
+        let msgs = merge_pages(pages, 2, 5).unwrap();
+        let ids: Vec<_> = msgs.iter().map(|envelope| envelope.id.as_str()).collect();
+        assert_eq!(ids, ["0", "1", "2", "3", "4"]);
+    }
+
+    #[test]
+    fn pages_under_another_base_are_rejected() {
+        // 1-based backend reading page 0 as page 1.
+        let overlapping = vec![page(0, 0..2), page(1, 0..2), page(2, 2..4), page(3, 4..5)];
+        assert!(merge_pages(overlapping, 2, 5).is_err());
+
+        // 1-based backend answering page 0 with nothing.
+        let shifted = vec![page(0, 0..0), page(1, 0..2), page(2, 2..4)];
+        assert!(merge_pages(shifted, 2, 5).is_err());
+    }
+
+    #[test]
+    fn pages_missing_messages_are_rejected() {
+        assert!(merge_pages(vec![page(0, 0..2), page(2, 4..5)], 2, 5).is_err());
+        assert!(merge_pages(vec![page(0, 0..2), page(1, 2..3)], 2, 5).is_err());
+    }
+
+    #[test]
+    fn copies_are_found_only_when_one_unclaimed_message_matches() {
+        let copied = envelope("7", "a@example.org", 1234);
+        let key = message_key(&copied);
+        let listed = vec![
+            envelope("1", "a@example.org", 1234),
+            envelope("2", "a@example.org", 999),
+            envelope("3", "b@example.org", 1234),
+        ];
+
+        let mut claimed = HashSet::new();
+        assert_eq!(find_copy(&listed, &claimed, key, &copied), Ok("1".into()));
+
+        claimed.insert("1".to_string());
+        assert_eq!(find_copy(&listed, &claimed, key, &copied), Err(0));
+
+        let twice = vec![
+            envelope("1", "a@example.org", 1234),
+            envelope("4", "a@example.org", 1234),
+        ];
+        let claimed = HashSet::new();
+        assert_eq!(find_copy(&twice, &claimed, key, &copied), Err(2));
+    }
+}
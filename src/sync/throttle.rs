@@ -0,0 +1,313 @@
+// This file is part of Neverest, a CLI to synchronize emails.
+//
+// Copyright (C) 2024-2026  soywod <pimalaya.org@posteo.net>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Adaptive throttling shared by the pool workers: server pressure
+//! signals halve the allowed concurrency and grow a per-request delay,
+//! a streak of calm requests slowly gives both back (AIMD). Each side
+//! has its own [`Throttle`], so one server pushing back never slows
+//! requests to the other.
+
+use std::{
+    sync::{Condvar, Mutex, MutexGuard},
+    thread,
+    time::Duration,
+};
+
+use anyhow::Error;
+use log::{debug, warn};
+
+use crate::{
+    side::Side,
+    sync::{report::ThrottleReport, retry},
+};
+
+/// Response fragments servers use to push back: IMAP response codes
+/// (RFC 5530) and HTTP statuses surfaced by the JMAP transport.
+/// `[OVERQUOTA]` is not one of them: slowing down never frees space.
+const THROTTLE_MARKERS: &[&str] = &[
+    "[THROTTLED]",
+    "[LIMIT]",
+    "429 Too Many Requests",
+    "503 Service Unavailable",
+];
+
+/// Delay applied on the first pressure signal without `Retry-After`.
+const INITIAL_DELAY: Duration = Duration::from_millis(250);
+
+/// Upper bound of the per-request delay, `Retry-After` included.
+const MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// Successful requests in a row needed before concurrency grows back
+/// by one slot and the delay halves.
+const RECOVERY_STREAK: u32 = 20;
+
+/// Whether `err` is the server asking us to slow down.
+pub fn is_throttled(err: &Error) -> bool {
+    let message = format!("{err:#}");
+    THROTTLE_MARKERS
+        .iter()
+        .any(|marker| message.contains(marker))
+}
+
+/// Delay requested by a `Retry-After: <seconds>` header quoted in
+/// `err`, if any.
+pub fn retry_after(err: &Error) -> Option<Duration> {
+    let message = format!("{err:#}").to_ascii_lowercase();
+    let (_, rest) = message.split_once("retry-after")?;
+    let digits: String = rest
+        .trim_start_matches([':', ' ', '='])
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect();
+    digits.parse().ok().map(Duration::from_secs)
+}
+
+/// Concurrency gate shared by every worker of a [`Pool`] run.
+///
+/// [`Pool`]: crate::sync::pool::Pool
+pub struct Throttle {
+    max: usize,
+    state: Mutex<ThrottleState>,
+    freed: Condvar,
+}
+
+struct ThrottleState {
+    /// Requests allowed in flight right now.
+    limit: usize,
+    /// Requests in flight.
+    active: usize,
+    /// Pause taken before each request.
+    delay: Duration,
+    calm_streak: u32,
+    report: ThrottleReport,
+}
+
+impl Throttle {
+    /// Starts unthrottled, with `max` requests to `side` allowed in
+    /// flight.
+    pub fn new(side: Side, max: usize) -> Self {
+        let max = max.max(1);
+        Self {
+            max,
+            state: Mutex::new(ThrottleState {
+                limit: max,
+                active: 0,
+                delay: Duration::ZERO,
+                calm_streak: 0,
+                report: ThrottleReport {
+                    side,
+                    events: 0,
+                    lowest_concurrency: max,
+                    longest_delay_ms: 0,
+                },
+            }),
+            freed: Condvar::new(),
+        }
+    }
+
+    /// Blocks until a slot frees up under the current limit, then
+    /// waits the current delay; the slot is released on drop.
+    pub fn acquire(&self) -> Permit<'_> {
+        let mut state = self.lock();
+        while state.active >= state.limit {
+            state = self
+                .freed
+                .wait(state)
+                .unwrap_or_else(|err| err.into_inner());
+        }
+        state.active += 1;
+        let delay = state.delay;
+        drop(state);
+
+        if !delay.is_zero() {
+            thread::sleep(delay);
+        }
+
+        Permit { throttle: self }
+    }
+
+    /// Runs a request outside the hunk workers (probe, listing) under
+    /// the gate, feeding its outcome back.
+    pub fn run<T>(&self, op: impl FnOnce() -> anyhow::Result<T>) -> anyhow::Result<T> {
+        let _permit = self.acquire();
+        let result = op();
+        self.observe(&result);
+        result
+    }
+
+    /// Feeds a request outcome back: pressure signals (throttling
+    /// codes, dropped connections) tighten the gate, calm streaks
+    /// loosen it.
+    pub fn observe<T>(&self, result: &anyhow::Result<T>) {
+        let mut state = self.lock();
+        match result {
+            Err(err) if is_throttled(err) || retry::is_disconnect(err) => {
+                state.limit = (state.limit / 2).max(1);
+                state.delay = retry_after(err)
+                    .unwrap_or((state.delay * 2).max(INITIAL_DELAY))
+                    .min(MAX_DELAY);
+                state.calm_streak = 0;
+
+                let (limit, delay) = (state.limit, state.delay);
+                let report = &mut state.report;
+                report.events += 1;
+                report.lowest_concurrency = report.lowest_concurrency.min(limit);
+                report.longest_delay_ms = report.longest_delay_ms.max(delay.as_millis() as u64);
+
+                warn!(
+                    "{} server pressure, slowing down to {} request(s) with {:?} delay: {err:#}",
+                    state.report.side, state.limit, state.delay
+                );
+            }
+            Err(_) => {}
+            Ok(_) => {
+                state.calm_streak += 1;
+                let relax = state.calm_streak >= RECOVERY_STREAK
+                    && (state.limit < self.max || !state.delay.is_zero());
+                if relax {
+                    state.calm_streak = 0;
+                    state.limit = (state.limit + 1).min(self.max);
+                    state.delay /= 2;
+                    if state.delay < INITIAL_DELAY / 4 {
+                        state.delay = Duration::ZERO;
+                    }
+                    debug!(
+                        "{} pressure eased, back to {} request(s) with {:?} delay",
+                        state.report.side, state.limit, state.delay
+                    );
+                    self.freed.notify_one();
+                }
+            }
+        }
+    }
+
+    /// Throttling recorded so far; `None` when the server never
+    /// pushed back.
+    pub fn report(&self) -> Option<ThrottleReport> {
+        let report = self.lock().report.clone();
+        (report.events > 0).then_some(report)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ThrottleState> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// One [`Throttle`] per side, each sized to that side's pool.
+pub struct Throttles {
+    pub left: Throttle,
+    pub right: Throttle,
+}
+
+impl Throttles {
+    pub fn new(left: usize, right: usize) -> Self {
+        Self {
+            left: Throttle::new(Side::Left, left),
+            right: Throttle::new(Side::Right, right),
+        }
+    }
+
+    pub fn side(&self, side: Side) -> &Throttle {
+        match side {
+            Side::Left => &self.left,
+            Side::Right => &self.right,
+        }
+    }
+
+    /// Throttling recorded so far, per side that pushed back.
+    pub fn report(&self) -> Vec<ThrottleReport> {
+        [&self.left, &self.right]
+            .into_iter()
+            .filter_map(Throttle::report)
+            .collect()
+    }
+}
+
+/// In-flight request slot handed out by [`Throttle::acquire`].
+pub struct Permit<'a> {
+    throttle: &'a Throttle,
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        self.throttle.lock().active -= 1;
+        self.throttle.freed.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use super::*;
+
+    #[test]
+    fn throttling_codes_are_detected() {
+        assert!(is_throttled(&anyhow!("NO [THROTTLED] Slow down")));
+        assert!(is_throttled(&anyhow!("HTTP 429 Too Many Requests")));
+        assert!(!is_throttled(&anyhow!("NO [NONEXISTENT] gone")));
+        assert!(!is_throttled(&anyhow!("NO [OVERQUOTA] Mailbox full")));
+    }
+
+    #[test]
+    fn retry_after_seconds_are_parsed() {
+        let err = anyhow!("503 Service Unavailable (Retry-After: 12)");
+        assert_eq!(retry_after(&err), Some(Duration::from_secs(12)));
+        assert_eq!(retry_after(&anyhow!("429 Too Many Requests")), None);
+    }
+
+    #[test]
+    fn pressure_halves_concurrency_and_recovery_restores_it() {
+        let throttle = Throttle::new(Side::Left, 8);
+        throttle.observe::<()>(&Err(anyhow!("NO [THROTTLED] Slow down")));
+        throttle.observe::<()>(&Err(anyhow!("NO [THROTTLED] Slow down")));
+
+        let report = throttle.report().unwrap();
+        assert_eq!(report.events, 2);
+        assert_eq!(report.lowest_concurrency, 2);
+        assert_eq!(report.longest_delay_ms, 500);
+
+        for _ in 0..RECOVERY_STREAK * 6 {
+            throttle.observe(&Ok(()));
+        }
+        let state = throttle.lock();
+        assert_eq!(state.limit, 8);
+        assert_eq!(state.delay, Duration::ZERO);
+    }
+
+    #[test]
+    fn pressure_on_one_side_leaves_the_other_alone() {
+        let throttles = Throttles::new(8, 4);
+        throttles
+            .right
+            .observe::<()>(&Err(anyhow!("NO [THROTTLED] Slow down")));
+
+        assert_eq!(throttles.left.lock().limit, 8);
+        assert_eq!(throttles.right.lock().limit, 2);
+        let reports = throttles.report();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].side, Side::Right);
+    }
+
+    #[test]
+    fn calm_runs_report_nothing() {
+        let throttle = Throttle::new(Side::Left, 4);
+        throttle.observe(&Ok(()));
+        throttle.observe::<()>(&Err(anyhow!("NO [NONEXISTENT] gone")));
+        assert!(throttle.report().is_none());
+    }
+}
@@ -28,7 +28,7 @@ use pimalaya_config::{command::shell, secret::Secret};
 
 use crate::config::{
     FlagSidePermissions, ImapConfig, JmapAuthConfig, JmapConfig, MailboxSidePermissions,
-    MessageSidePermissions, SaslConfig, SaslPlainConfig,
+    MessageSidePermissions, PoolSize, SaslConfig, SaslPlainConfig,
 };
 
 /// Converts wizard IMAP answers into an on-disk [`ImapConfig`].
@@ -49,7 +49,7 @@ pub fn imap_to_config(w: WizardImapConfig) -> Result<ImapConfig> {
         mailbox: MailboxSidePermissions::default(),
         flag: FlagSidePermissions::default(),
         message: MessageSidePermissions::default(),
-        pool_size: None,
+        pool_size: PoolSize::Auto,
     })
 }
 
@@ -74,7 +74,7 @@ pub fn jmap_to_config(w: WizardJmapConfig) -> Result<JmapConfig> {
         mailbox: MailboxSidePermissions::default(),
         flag: FlagSidePermissions::default(),
         message: MessageSidePermissions::default(),
-        pool_size: None,
+        pool_size: PoolSize::Auto,
     })
 }
 
@@ -39,7 +39,7 @@ use url::Url;
 use crate::{
     config::{
         AccountConfig, Config, FlagSidePermissions, M2dirConfig, MailboxSidePermissions,
-        MessageSidePermissions, SideConfig,
+        MessageSidePermissions, PoolSize, SideConfig,
     },
     wizard::{
         account::{imap_to_config, jmap_to_config},
@@ -166,7 +166,7 @@ fn build_account_from_discovery(
         mailbox: MailboxSidePermissions::default(),
         flag: FlagSidePermissions::default(),
         message: MessageSidePermissions::default(),
-        pool_size: None,
+        pool_size: PoolSize::Auto,
     });
 
     Ok(AccountConfig {
@@ -175,5 +175,7 @@ fn build_account_from_discovery(
         right,
         mailbox: Default::default(),
         message: Default::default(),
+        retry: Default::default(),
+        hooks: Default::default(),
     })
 }
@@ -36,7 +36,8 @@ use pimalaya_cli::{
 use crate::{
     config::{
         AccountConfig, Config, FlagSidePermissions, ImapConfig, JmapAuthConfig, JmapConfig,
-        M2dirConfig, MailboxSidePermissions, MessageSidePermissions, SaslConfig, SideConfig,
+        M2dirConfig, MailboxSidePermissions, MessageSidePermissions, PoolSize, SaslConfig,
+        SideConfig,
     },
     wizard::account::{imap_to_config, jmap_to_config},
 };
@@ -72,6 +73,14 @@ pub fn edit_account(target: &Path, mut config: Config, account_name: &str) -> Re
         .as_ref()
         .map(|a| a.message.clone())
         .unwrap_or_default();
+    let retry = existing
+        .as_ref()
+        .map(|a| a.retry.clone())
+        .unwrap_or_default();
+    let hooks = existing
+        .as_ref()
+        .map(|a| a.hooks.clone())
+        .unwrap_or_default();
 
     let left = prompt_side("left", local_part, domain, account_name, left_default)?;
     let right = prompt_side("right", local_part, domain, account_name, right_default)?;
@@ -82,6 +91,8 @@ pub fn edit_account(target: &Path, mut config: Config, account_name: &str) -> Re
         right,
         mailbox,
         message,
+        retry,
+        hooks,
     };
 
     config.accounts.insert(account_name.to_owned(), account);
@@ -133,7 +144,7 @@ fn prompt_side(
                 mailbox: MailboxSidePermissions::default(),
                 flag: FlagSidePermissions::default(),
                 message: MessageSidePermissions::default(),
-                pool_size: None,
+                pool_size: PoolSize::Auto,
             }))
         }
     }